@@ -0,0 +1,1097 @@
+use anyhow::{anyhow, Result};
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, U256},
+};
+use futures::stream::{self, StreamExt};
+use log::{info, warn};
+use rand::seq::SliceRandom;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::blob::{BlobProofs, BlobSidecar};
+use crate::block_watcher::BlockWatcher;
+use crate::confirmation::{ConfirmationTarget, Finality};
+use crate::export::{redact_url, EndpointMetadata, RunMetadata};
+use crate::gas::GasStrategy;
+use crate::head_tracker::{millis_since_epoch, HeadTracker};
+use crate::nonce::{self, NonceManager};
+use crate::observer::Observer;
+use crate::probe::{self, ProbeResult};
+use crate::replacement::{StuckAction, StuckPolicy};
+use crate::report::{BlockTiming, PhaseTimings, Report, TxResult, TxStatus};
+use crate::submitter::{PollStrategy, Submitter, TxMethod, TxType};
+use crate::transport::{record_http_timings, Transport};
+use crate::wallets;
+use crate::workload::Workload;
+use crate::Client;
+
+/// How the submission method is chosen
+#[derive(Debug, Clone, Copy)]
+enum MethodChoice {
+    Fixed(TxMethod),
+    /// Probe the endpoints and use the best method they all support
+    Auto,
+    /// Probe the endpoints and interleave every method they all support
+    All,
+}
+
+/// Builder for a [`Benchmark`]; at least one RPC URL and `private_key` are required
+#[derive(Debug, Clone)]
+pub struct BenchmarkBuilder {
+    /// Endpoints under test as (label, URL); unlabeled ones are named after their host
+    endpoints: Vec<(Option<String>, String)>,
+    private_key: Option<String>,
+    method: MethodChoice,
+    num_transactions: u64,
+    concurrency: usize,
+    rate: Option<f64>,
+    duration: Option<Duration>,
+    ws_url: Option<String>,
+    poll_strategy: PollStrategy,
+    http_timing: bool,
+    warmup: u64,
+    observers: Vec<String>,
+    observe_interval: Duration,
+    observe_timeout: Duration,
+    block_timing: bool,
+    head_poll_interval: Duration,
+    confirmation_target: ConfirmationTarget,
+    stuck_policy: Option<StuckPolicy>,
+    /// Evaluated per transaction when set; otherwise the default strategy is evaluated once at setup
+    gas_strategy: Option<GasStrategy>,
+    /// Priority fees the measured transactions are spread over; empty unless sweeping tips
+    tip_levels: Vec<U256>,
+    workload: Workload,
+    gas_limit: Option<u64>,
+    tx_type: TxType,
+    blob_proofs: BlobProofs,
+    /// Number of derived sender wallets; `None` sends from the main wallet itself
+    wallets: Option<usize>,
+    mnemonic: Option<String>,
+}
+
+impl Default for BenchmarkBuilder {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            private_key: None,
+            method: MethodChoice::Fixed(TxMethod::Async),
+            num_transactions: 10,
+            concurrency: 1,
+            rate: None,
+            duration: None,
+            ws_url: None,
+            poll_strategy: PollStrategy::default(),
+            http_timing: false,
+            warmup: 0,
+            observers: Vec::new(),
+            observe_interval: Duration::from_millis(50),
+            observe_timeout: Duration::from_secs(30),
+            block_timing: false,
+            head_poll_interval: Duration::from_millis(100),
+            confirmation_target: ConfirmationTarget::default(),
+            stuck_policy: None,
+            gas_strategy: None,
+            tip_levels: Vec::new(),
+            workload: Workload::SelfTransfer,
+            gas_limit: None,
+            tx_type: TxType::default(),
+            blob_proofs: BlobProofs::default(),
+            wallets: None,
+            mnemonic: None,
+        }
+    }
+}
+
+impl BenchmarkBuilder {
+    /// RPC endpoint URL. Call it again to compare several endpoints: transactions are sent to
+    /// them round-robin and the report breaks latency down per endpoint.
+    pub fn rpc_url(mut self, rpc_url: impl Into<String>) -> Self {
+        self.endpoints.push((None, rpc_url.into()));
+        self
+    }
+
+    /// Like [`rpc_url`](Self::rpc_url), with the label shown for the endpoint in reports
+    pub fn endpoint(mut self, label: impl Into<String>, rpc_url: impl Into<String>) -> Self {
+        self.endpoints.push((Some(label.into()), rpc_url.into()));
+        self
+    }
+
+    /// Private key of the funded wallet that sends the transactions
+    pub fn private_key(mut self, private_key: impl Into<String>) -> Self {
+        self.private_key = Some(private_key.into());
+        self
+    }
+
+    /// Transaction submission method (default: async)
+    pub fn method(mut self, method: TxMethod) -> Self {
+        self.method = MethodChoice::Fixed(method);
+        self
+    }
+
+    /// Probe the endpoint when building and use the best submission method it supports
+    pub fn auto_method(mut self) -> Self {
+        self.method = MethodChoice::Auto;
+        self
+    }
+
+    /// Probe the endpoint when building and compare every submission method it supports:
+    /// transactions cycle through the methods, so each one sees the same network conditions
+    pub fn all_methods(mut self) -> Self {
+        self.method = MethodChoice::All;
+        self
+    }
+
+    /// Number of transactions to send (default: 10); ignored if both `rate` and `duration` are set
+    pub fn num_transactions(mut self, num_transactions: u64) -> Self {
+        self.num_transactions = num_transactions;
+        self
+    }
+
+    /// Number of transactions to keep in flight in closed-loop mode (default: 1, sequential)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Switch to open-loop mode, sending `rate` transactions per second regardless of completions
+    pub fn rate(mut self, rate: f64) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// How long to run in open-loop mode; the number of transactions becomes `rate * duration`
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// WebSocket endpoint whose `newHeads` subscription detects async confirmations,
+    /// instead of polling for the receipt every 100 ms
+    pub fn ws_url(mut self, ws_url: impl Into<String>) -> Self {
+        self.ws_url = Some(ws_url.into());
+        self
+    }
+
+    /// How the async method polls for receipts (default: every 100 ms)
+    pub fn poll_strategy(mut self, poll_strategy: PollStrategy) -> Self {
+        self.poll_strategy = poll_strategy;
+        self
+    }
+
+    /// Send JSON-RPC calls over an instrumented HTTP transport that records DNS, TCP connect,
+    /// TLS handshake, write and time-to-first-byte for every call (default: off)
+    pub fn http_timing(mut self, http_timing: bool) -> Self {
+        self.http_timing = http_timing;
+        self
+    }
+
+    /// Send `warmup` transactions first, the same way as the measured ones, and exclude them from
+    /// the statistics (default: 0)
+    pub fn warmup(mut self, warmup: u64) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Watch another endpoint for every transaction, recording when it first knows the
+    /// transaction and when it has the receipt. A `ws://` or `wss://` URL is followed over a
+    /// `newPendingTransactions` subscription, an HTTP URL is polled with `eth_getTransactionByHash`.
+    pub fn observer(mut self, url: impl Into<String>) -> Self {
+        self.observers.push(url.into());
+        self
+    }
+
+    /// How often observers are polled (default: 50 ms)
+    pub fn observe_interval(mut self, interval: Duration) -> Self {
+        self.observe_interval = interval;
+        self
+    }
+
+    /// How long observers are watched for a transaction before giving up (default: 30 s)
+    pub fn observe_timeout(mut self, timeout: Duration) -> Self {
+        self.observe_timeout = timeout;
+        self
+    }
+
+    /// Fetch the header of every including block and compare its timestamp with the local send
+    /// time, estimate the local clock's offset from block timestamps, and count the blocks between
+    /// the head at send time and inclusion (default: off)
+    pub fn block_timing(mut self, block_timing: bool) -> Self {
+        self.block_timing = block_timing;
+        self
+    }
+
+    /// How often the chain head is polled for block timing (default: 100 ms); the head at send
+    /// time is only as fresh as this interval
+    pub fn head_poll_interval(mut self, interval: Duration) -> Self {
+        self.head_poll_interval = interval;
+        self
+    }
+
+    /// Keep tracking each included transaction until its block has `confirmations` blocks on top,
+    /// counting itself (default: 1, the first receipt)
+    pub fn confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmation_target.confirmations = confirmations.max(1);
+        self
+    }
+
+    /// Keep tracking each included transaction until its block is covered by the `safe` or
+    /// `finalized` tag
+    pub fn finality(mut self, finality: Finality) -> Self {
+        self.confirmation_target.finality = Some(finality);
+        self
+    }
+
+    /// Stop tracking a transaction's confirmations or finality this long after its first receipt,
+    /// e.g. when the chain doesn't serve the finality tag or the transaction was reorged out and
+    /// never re-included; it is then counted as timed out (default: 20 minutes)
+    pub fn confirmation_timeout(mut self, timeout: Duration) -> Self {
+        self.confirmation_target.timeout = timeout;
+        self
+    }
+
+    /// Rebroadcast, speed up or cancel async transactions that still have no receipt after the
+    /// policy's timeout, instead of waiting for the receipt indefinitely. Replacements share the
+    /// original's nonce, and the report records which transaction landed.
+    pub fn stuck_policy(mut self, stuck_policy: StuckPolicy) -> Self {
+        self.stuck_policy = Some(stuck_policy);
+        self
+    }
+
+    /// How the fees of every transaction are chosen, evaluated again for each one so they follow
+    /// the chain. Without it, 3x `eth_gasPrice` with a 1 gwei priority fee is evaluated once at
+    /// setup and used for the whole run.
+    pub fn gas_strategy(mut self, gas_strategy: GasStrategy) -> Self {
+        self.gas_strategy = Some(gas_strategy);
+        self
+    }
+
+    /// Sweep the priority fee: the measured transactions are spread evenly over `tip_levels` in
+    /// random order, each paying its level on top of the latest base fee, and the report breaks
+    /// latency and blocks to inclusion down per level. Takes precedence over the gas strategy,
+    /// and enables block timing to count the blocks.
+    pub fn tip_sweep(mut self, tip_levels: impl IntoIterator<Item = U256>) -> Self {
+        self.tip_levels = tip_levels.into_iter().collect();
+        self
+    }
+
+    /// What every transaction does (default: zero-value self-transfers)
+    pub fn workload(mut self, workload: Workload) -> Self {
+        self.workload = workload;
+        self
+    }
+
+    /// Fixed gas limit for every transaction, instead of 21000 for self-transfers, the burn gas
+    /// for burns and `eth_estimateGas` for everything else
+    pub fn gas_limit(mut self, gas_limit: u64) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Transaction type used for every method, so methods are compared like-for-like
+    /// (default: EIP-1559)
+    pub fn tx_type(mut self, tx_type: TxType) -> Self {
+        self.tx_type = tx_type;
+        self
+    }
+
+    /// Which KZG proofs blob transactions carry: per-blob proofs before the Osaka upgrade, cell
+    /// proofs from it on (default: cell)
+    pub fn blob_proofs(mut self, blob_proofs: BlobProofs) -> Self {
+        self.blob_proofs = blob_proofs;
+        self
+    }
+
+    /// Send from `wallets` derived sender wallets instead of the main wallet, each with its own
+    /// nonce sequence. The main wallet funds them while building, and [`Benchmark::sweep`] sends
+    /// what is left back.
+    pub fn wallets(mut self, wallets: usize) -> Self {
+        self.wallets = Some(wallets.max(1));
+        self
+    }
+
+    /// Derive the sender wallets from this BIP-39 mnemonic rather than from the main key
+    pub fn mnemonic(mut self, mnemonic: impl Into<String>) -> Self {
+        self.mnemonic = Some(mnemonic.into());
+        self
+    }
+
+    /// Connects to the RPC endpoints, deploys the workload's contracts if needed, funds the sender
+    /// wallets, and fetches the chain ID, starting nonces, gas price and gas limit
+    pub async fn build(self) -> Result<Benchmark> {
+        if self.endpoints.is_empty() {
+            return Err(anyhow!("an RPC URL is required"));
+        }
+        let private_key = self.private_key.ok_or_else(|| anyhow!("a private key is required"))?;
+        // Blob transactions are encoded here rather than by ethers, which can't replace them
+        let blob_sidecar = match self.tx_type {
+            TxType::Eip4844 => {
+                if matches!(self.workload, Workload::Deploy { .. }) {
+                    return Err(anyhow!("blob transactions can't deploy contracts; choose another workload or transaction type"));
+                }
+                if let Some(stuck_policy) = self.stuck_policy
+                    && stuck_policy.action != StuckAction::Rebroadcast
+                {
+                    return Err(anyhow!("blob transactions can only be rebroadcast when stuck, not sped up or cancelled"));
+                }
+                Some(Arc::new(BlobSidecar::generate(self.blob_proofs)?))
+            }
+            _ => None,
+        };
+        let wallet: LocalWallet = private_key.parse()?;
+
+        // Connect to every endpoint and make sure they all serve the same chain
+        let mut providers = Vec::with_capacity(self.endpoints.len());
+        let mut chain_id = None;
+        for (_, rpc_url) in &self.endpoints {
+            let transport = if self.http_timing {
+                Transport::instrumented(rpc_url)?
+            } else {
+                Transport::http(rpc_url)?
+            };
+            let provider = Provider::new(transport);
+            let endpoint_chain_id = provider.get_chainid().await?.as_u64();
+            match chain_id {
+                Some(chain_id) if chain_id != endpoint_chain_id => {
+                    return Err(anyhow!(
+                        "{} is on chain {}, but the first endpoint is on chain {}",
+                        redact_url(rpc_url), endpoint_chain_id, chain_id
+                    ));
+                }
+                _ => chain_id = Some(endpoint_chain_id),
+            }
+            providers.push(provider);
+        }
+        let chain_id = chain_id.unwrap_or_default();
+        let wallet = wallet.with_chain_id(chain_id);
+
+        // Every endpoint uses the same methods, so only methods they all support are considered
+        let probe_results = match self.method {
+            MethodChoice::Fixed(_) => vec![Vec::new(); providers.len()],
+            MethodChoice::Auto | MethodChoice::All => {
+                let mut results = Vec::with_capacity(providers.len());
+                for provider in &providers {
+                    results.push(probe::probe_all(provider).await);
+                }
+                results
+            }
+        };
+        let methods = match self.method {
+            MethodChoice::Fixed(method) => vec![method],
+            MethodChoice::Auto => vec![probe::select_common_method(&probe_results)],
+            MethodChoice::All => {
+                let methods = probe::common_methods(&probe_results);
+                for method in probe::COMPARISON_ORDER {
+                    if !methods.contains(&method) {
+                        warn!("Skipping the {} method: not supported by every endpoint", method);
+                    }
+                }
+                if methods.is_empty() {
+                    return Err(anyhow!("no submission method is supported by every endpoint"));
+                }
+                methods
+            }
+        };
+
+        let sender_wallets = match self.wallets {
+            Some(count) => wallets::derive(&wallet, self.mnemonic.as_deref(), count)?,
+            None => vec![wallet.clone()],
+        };
+
+        // Create standard ethers middleware: one client per endpoint for every sender,
+        // and one for the main wallet, which sets up the run
+        let clients: Vec<Vec<Arc<Client>>> = providers
+            .iter()
+            .map(|provider| {
+                sender_wallets
+                    .iter()
+                    .map(|sender| Arc::new(SignerMiddleware::new(provider.clone(), sender.clone())))
+                    .collect()
+            })
+            .collect();
+        let funder = Arc::new(SignerMiddleware::new(providers[0].clone(), wallet));
+        let client = &funder;
+
+        // Setup transactions take nonces of their own, so they go before the starting nonce is read
+        let mut workload = self.workload;
+        workload.deploy_dependencies(client).await?;
+        let gas_limit = match self.gas_limit {
+            Some(gas_limit) => gas_limit,
+            None => workload.gas_limit(client).await?,
+        };
+
+        // Make necessary RPC calls before the transaction loop
+        let mut senders = Vec::with_capacity(sender_wallets.len());
+        for sender in &sender_wallets {
+            let starting_nonce = client.get_transaction_count(sender.address(), None).await?.as_u64();
+            senders.push(Arc::new(Sender {
+                address: sender.address(),
+                starting_nonce,
+                nonces: NonceManager::new(starting_nonce),
+            }));
+        }
+        let default_gas_price = client.get_gas_price().await?;
+        if default_gas_price.is_zero() {
+            warn!("RPC returned zero gas price, using 1 gwei as default");
+        }
+        // Setup transactions, gap fillers and sweeps pay the strategy's fees at setup. Without a
+        // strategy, benchmark transactions pay them too, so the fees stay fixed for the run.
+        let setup_fees = self.gas_strategy.unwrap_or_default().fees(client).await?;
+        let gas_price = setup_fees.gas_price;
+        let mut tip_levels = self.tip_levels;
+        tip_levels.sort();
+        tip_levels.dedup();
+        // Warm-up transactions and the funding of the senders go by the highest tip of a sweep
+        let gas_strategy = match tip_levels.last() {
+            Some(&priority_fee) => GasStrategy::BaseFeePlus { priority_fee },
+            None => self.gas_strategy.unwrap_or(GasStrategy::Fixed {
+                gas_price,
+                priority_fee: setup_fees.max_priority_fee_per_gas,
+            }),
+        };
+
+        // Get number of transactions directly, or from rate x duration in open-loop mode
+        let num_transactions = match (self.rate, self.duration) {
+            (Some(rate), Some(duration)) => (rate * duration.as_secs_f64()).floor() as u64,
+            _ => self.num_transactions,
+        };
+
+        // Only the async method waits for confirmations; the sync methods get the receipt back directly.
+        // Inclusion is chain-wide, so one subscription serves every endpoint.
+        let (ws_url, block_watcher) = match self.ws_url {
+            Some(ws_url) if methods.contains(&TxMethod::Async) => {
+                let block_watcher = Arc::new(BlockWatcher::connect(&ws_url).await?);
+                (Some(ws_url), Some(block_watcher))
+            }
+            Some(_) => {
+                warn!("Ignoring the WebSocket URL: the {} method needs no confirmation polling", methods[0]);
+                (None, None)
+            }
+            None => (None, None),
+        };
+
+        let observer_urls: Vec<(Option<String>, String)> =
+            self.observers.iter().map(|url| (None, url.clone())).collect();
+        let mut observers = Vec::with_capacity(observer_urls.len());
+        for (label, (_, url)) in endpoint_labels(&observer_urls).into_iter().zip(&observer_urls) {
+            let observer = Observer::connect(label, url, self.observe_interval, self.observe_timeout).await?;
+            observers.push(Arc::new(observer));
+        }
+
+        // Start following the head now, so the clock offset has samples by the first transaction
+        let head_tracker = if self.block_timing || !tip_levels.is_empty() {
+            Some(Arc::new(HeadTracker::start(funder.clone(), self.head_poll_interval).await?))
+        } else {
+            None
+        };
+
+        let labels = endpoint_labels(&self.endpoints);
+        let endpoints = self
+            .endpoints
+            .into_iter()
+            .zip(labels)
+            .zip(clients.into_iter().zip(probe_results))
+            .map(|(((_, rpc_url), label), (clients, probe_results))| {
+                let submitters = clients
+                    .iter()
+                    .flat_map(|client| methods.iter().map(move |&method| (client, method)))
+                    .map(|(client, method)| {
+                        let submitter = Submitter::new(client.clone(), method, gas_price)
+                            .with_poll_strategy(self.poll_strategy)
+                            .with_observers(observers.clone())
+                            .with_confirmation_target(self.confirmation_target)
+                            .with_workload(workload.clone(), gas_limit)
+                            .with_tx_type(self.tx_type)
+                            .with_gas_strategy(gas_strategy);
+                        let submitter = match self.stuck_policy {
+                            Some(stuck_policy) => submitter.with_stuck_policy(stuck_policy),
+                            None => submitter,
+                        };
+                        let submitter = match &blob_sidecar {
+                            Some(blob_sidecar) => submitter.with_blob_sidecar(blob_sidecar.clone()),
+                            None => submitter,
+                        };
+                        match &block_watcher {
+                            Some(block_watcher) => submitter.with_block_watcher(block_watcher.clone()),
+                            None => submitter,
+                        }
+                    })
+                    .collect();
+                Arc::new(Endpoint { label, rpc_url, submitters, probe_results })
+            })
+            .collect::<Vec<_>>();
+
+        // Every sender gets enough for its share of the transactions at the highest fee they may pay
+        let derived_wallets = self.wallets.is_some();
+        if derived_wallets {
+            let per_sender = (self.warmup + num_transactions).div_ceil(senders.len() as u64);
+            let amount = endpoints[0].submitters[0].max_cost().await? * per_sender;
+            let addresses: Vec<Address> = senders.iter().map(|s| s.address).collect();
+            info!("Funding {} sender wallets with {} wei each...", addresses.len(), amount);
+            wallets::fund(&funder, &addresses, amount, gas_price).await?;
+            // ERC-20 senders also need their share of the tokens
+            if let Workload::Erc20 { token, amount, .. } = workload {
+                let tokens = amount * per_sender;
+                info!("Funding {} sender wallets with {} tokens each...", addresses.len(), tokens);
+                wallets::fund_tokens(&funder, token, &addresses, tokens, gas_price).await?;
+            }
+        }
+
+        Ok(Benchmark {
+            endpoints,
+            methods,
+            observers,
+            head_tracker,
+            funder,
+            senders,
+            derived_wallets,
+            ws_url,
+            chain_id,
+            default_gas_price,
+            tip_levels,
+            num_transactions,
+            warmup: self.warmup,
+            concurrency: self.concurrency,
+            rate: self.rate,
+        })
+    }
+}
+
+/// Labels for the endpoints: the given label, or the URL's host (and port), numbered if the
+/// same host appears more than once
+fn endpoint_labels(endpoints: &[(Option<String>, String)]) -> Vec<String> {
+    let hosts: Vec<String> = endpoints
+        .iter()
+        .map(|(label, rpc_url)| {
+            label.clone().unwrap_or_else(|| match url::Url::parse(rpc_url) {
+                Ok(url) => match (url.host_str(), url.port()) {
+                    (Some(host), Some(port)) => format!("{}:{}", host, port),
+                    (Some(host), None) => host.to_string(),
+                    _ => redact_url(rpc_url),
+                },
+                Err(_) => redact_url(rpc_url),
+            })
+        })
+        .collect();
+
+    hosts
+        .iter()
+        .enumerate()
+        .map(|(i, host)| {
+            let occurrences = hosts.iter().filter(|h| *h == host).count();
+            if occurrences > 1 {
+                let nth = hosts[..=i].iter().filter(|h| *h == host).count();
+                format!("{}#{}", host, nth)
+            } else {
+                host.clone()
+            }
+        })
+        .collect()
+}
+
+/// A wallet that sends benchmark transactions, with its own nonce sequence shared by every endpoint
+#[derive(Debug)]
+struct Sender {
+    address: Address,
+    starting_nonce: u64,
+    nonces: NonceManager,
+}
+
+/// One RPC endpoint under test, with its own client and connection
+#[derive(Debug)]
+pub struct Endpoint {
+    label: String,
+    rpc_url: String,
+    /// One per sender wallet and method under test: every method in [`Benchmark::methods`] order
+    /// for the first sender, then for the next one
+    submitters: Vec<Submitter>,
+    probe_results: Vec<ProbeResult>,
+}
+
+impl Endpoint {
+    /// Short name used for the endpoint in reports
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+
+    /// Submitters for each sender wallet and method under test, grouped by sender
+    pub fn submitters(&self) -> &[Submitter] {
+        &self.submitters
+    }
+
+    /// What the endpoint answered for each method when the method was auto-selected or all
+    /// methods are compared; empty when it was chosen explicitly
+    pub fn probe_results(&self) -> &[ProbeResult] {
+        &self.probe_results
+    }
+}
+
+/// A configured benchmark, ready to run against one or more connected endpoints
+#[derive(Debug)]
+pub struct Benchmark {
+    endpoints: Vec<Arc<Endpoint>>,
+    /// Methods under test; more than one when comparing methods
+    methods: Vec<TxMethod>,
+    /// Endpoints watched for propagation of every transaction
+    observers: Vec<Arc<Observer>>,
+    /// Set when block timing is enabled
+    head_tracker: Option<Arc<HeadTracker>>,
+    /// Main wallet, which deploys the workload's contracts and funds the sender wallets
+    funder: Arc<Client>,
+    /// Wallets transactions are sent from, round-robin; just the main wallet unless wallets are derived
+    senders: Vec<Arc<Sender>>,
+    /// Whether the senders were derived and funded, and are to be swept back
+    derived_wallets: bool,
+    /// Set when async confirmations come from a `newHeads` subscription
+    ws_url: Option<String>,
+    chain_id: u64,
+    default_gas_price: U256,
+    /// Priority fees of a tip sweep, ascending; empty otherwise
+    tip_levels: Vec<U256>,
+    num_transactions: u64,
+    warmup: u64,
+    concurrency: usize,
+    rate: Option<f64>,
+}
+
+impl Benchmark {
+    pub fn builder() -> BenchmarkBuilder {
+        BenchmarkBuilder::default()
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// The endpoints under test, in the order transactions are sent to them
+    pub fn endpoints(&self) -> &[Arc<Endpoint>] {
+        &self.endpoints
+    }
+
+    /// The main wallet, which funds the sender wallets when they are derived
+    pub fn wallet_address(&self) -> Address {
+        self.funder.address()
+    }
+
+    /// Wallets transactions are sent from, in round-robin order
+    pub fn sender_addresses(&self) -> Vec<Address> {
+        self.senders.iter().map(|s| s.address).collect()
+    }
+
+    /// Starting nonce of the first sender wallet
+    pub fn starting_nonce(&self) -> u64 {
+        self.senders[0].starting_nonce
+    }
+
+    /// Gas price reported by the endpoint
+    pub fn default_gas_price(&self) -> U256 {
+        self.default_gas_price
+    }
+
+    /// Gas price chosen at setup, used for every benchmark transaction unless a gas strategy was given
+    pub fn gas_price(&self) -> U256 {
+        self.submitter().gas_price()
+    }
+
+    /// How the fees of every transaction are chosen
+    pub fn gas_strategy(&self) -> GasStrategy {
+        self.submitter().gas_strategy()
+    }
+
+    /// Priority fees swept over, ascending; empty unless sweeping tips
+    pub fn tip_levels(&self) -> &[U256] {
+        &self.tip_levels
+    }
+
+    /// The method under test, or the first one when comparing methods
+    pub fn method(&self) -> TxMethod {
+        self.methods[0]
+    }
+
+    /// Endpoints watched for propagation of every transaction
+    pub fn observers(&self) -> &[Arc<Observer>] {
+        &self.observers
+    }
+
+    /// What every transaction does
+    pub fn workload(&self) -> &Workload {
+        self.submitter().workload()
+    }
+
+    /// Gas limit of every transaction
+    pub fn gas_limit(&self) -> u64 {
+        self.submitter().gas_limit()
+    }
+
+    /// Transaction type used for every method
+    pub fn tx_type(&self) -> TxType {
+        self.submitter().tx_type()
+    }
+
+    /// Which KZG proofs blob transactions carry, if the transaction type is 4844
+    pub fn blob_proofs(&self) -> Option<BlobProofs> {
+        self.submitter().blob_proofs()
+    }
+
+    /// What is done with async transactions that have no receipt after a while, if anything
+    pub fn stuck_policy(&self) -> Option<StuckPolicy> {
+        self.submitter().stuck_policy()
+    }
+
+    /// Methods under test, in the order transactions cycle through them
+    pub fn methods(&self) -> &[TxMethod] {
+        &self.methods
+    }
+
+    /// First submitter of the first endpoint; all submitters share the same gas and polling settings
+    fn submitter(&self) -> &Submitter {
+        &self.endpoints[0].submitters[0]
+    }
+
+    /// WebSocket endpoint used to detect confirmations, if any
+    pub fn ws_url(&self) -> Option<&str> {
+        self.ws_url.as_deref()
+    }
+
+    pub fn num_transactions(&self) -> u64 {
+        self.num_transactions
+    }
+
+    /// Number of warm-up transactions sent before the measured ones
+    pub fn warmup(&self) -> u64 {
+        self.warmup
+    }
+
+    /// Sends all transactions and collects the results
+    pub async fn run(&self) -> Report {
+        let mut metadata = RunMetadata::new(
+            self.chain_id,
+            &self.endpoints[0].rpc_url,
+            self.method(),
+            self.gas_price(),
+            self.num_transactions,
+            self.concurrency,
+            self.rate,
+        );
+        metadata.ws_url = self.ws_url.as_deref().map(redact_url);
+        metadata.observers = self
+            .observers
+            .iter()
+            .map(|o| EndpointMetadata { label: o.label().to_string(), rpc_url: redact_url(o.url()) })
+            .collect();
+        let target = self.submitter().confirmation_target();
+        metadata.confirmations = (target.confirmations > 1).then_some(target.confirmations);
+        metadata.finality = target.finality;
+        if target.is_beyond_receipt() {
+            metadata.confirmation_timeout_ms = Some(target.timeout.as_secs_f64() * 1000.0);
+        }
+        if self.methods.len() > 1 {
+            metadata.methods = self.methods.clone();
+        }
+        if self.methods.contains(&TxMethod::Async) {
+            metadata.poll_strategy = Some(self.submitter().poll_strategy().to_string());
+            metadata.stuck_policy = self.stuck_policy().map(|p| p.to_string());
+        }
+        metadata.warmup = self.warmup;
+        metadata.workload = self.workload().name();
+        metadata.gas_limit = self.gas_limit();
+        metadata.tx_type = self.tx_type();
+        metadata.blob_proofs = self.blob_proofs();
+        metadata.gas_strategy = if self.tip_levels.is_empty() {
+            self.gas_strategy().to_string()
+        } else {
+            "latest base fee + swept tip".to_string()
+        };
+        metadata.tip_levels_wei = self.tip_levels.iter().map(ToString::to_string).collect();
+        if self.derived_wallets {
+            metadata.wallets = self.sender_addresses();
+        }
+        if self.endpoints.len() > 1 {
+            metadata.endpoints = self
+                .endpoints
+                .iter()
+                .map(|e| EndpointMetadata { label: e.label.clone(), rpc_url: redact_url(&e.rpc_url) })
+                .collect();
+        }
+
+        // Warm-up transactions take the first nonces and pay for connection setup and cold caches
+        let mut warmup_results = Vec::new();
+        if self.warmup > 0 {
+            info!("\nSending {} warm-up transactions (excluded from statistics)...", self.warmup);
+            warmup_results = self.send_batch(self.warmup, &[]).await;
+            warmup_results.sort_by_key(|r| r.index);
+            for r in &mut warmup_results {
+                r.warmup = true;
+            }
+        }
+
+        // Start timer for entire batch
+        let batch_start_time = Instant::now();
+
+        match self.rate {
+            Some(rate) => info!("\nSending {} transactions at {:.2} tx/s, independent of confirmations...", self.num_transactions, rate),
+            None if self.concurrency == 1 => info!("\nSending {} transactions sequentially, waiting for confirmation after each...", self.num_transactions),
+            None => info!("\nSending {} transactions with up to {} in flight...", self.num_transactions, self.concurrency),
+        }
+        let tips = self.tip_schedule(self.num_transactions);
+        let mut results = self.send_batch(self.num_transactions, &tips).await;
+        let elapsed = batch_start_time.elapsed();
+        results.sort_by_key(|r| r.index);
+        warmup_results.append(&mut results);
+
+        if let Some((offset, blocks)) = self.head_tracker.as_ref().and_then(|t| t.clock_offset()) {
+            metadata.clock_offset_ms = Some(offset);
+            metadata.clock_offset_blocks = Some(blocks);
+        }
+
+        Report {
+            metadata,
+            results: warmup_results,
+            elapsed,
+        }
+    }
+
+    /// Sends what is left on the derived sender wallets, the ERC-20 workload's tokens first, back
+    /// to the main wallet and returns the total amounts of ETH and tokens swept. Does nothing when
+    /// sending from the main wallet itself.
+    pub async fn sweep(&self) -> (U256, U256) {
+        if !self.derived_wallets {
+            return (U256::zero(), U256::zero());
+        }
+        let senders: Vec<&Client> = (0..self.senders.len())
+            .map(|i| self.endpoints[0].submitters[i * self.methods.len()].client().as_ref())
+            .collect();
+        let gas_strategy = self.gas_strategy();
+        let tokens = match self.workload() {
+            Workload::Erc20 { token, .. } => wallets::sweep_tokens(&senders, *token, self.wallet_address(), &gas_strategy).await,
+            _ => U256::zero(),
+        };
+        let wei = wallets::sweep(&senders, self.wallet_address(), &gas_strategy).await;
+        (wei, tokens)
+    }
+
+    /// Tip of each of `count` transactions in a sweep: every level equally often, give or take
+    /// one, shuffled so that changes in the chain's load over the run don't favor any level.
+    /// Empty when not sweeping.
+    fn tip_schedule(&self, count: u64) -> Vec<U256> {
+        if self.tip_levels.is_empty() {
+            return Vec::new();
+        }
+        let mut tips: Vec<U256> = (0..count as usize).map(|i| self.tip_levels[i % self.tip_levels.len()]).collect();
+        tips.shuffle(&mut rand::thread_rng());
+        tips
+    }
+
+    /// Sends `count` transactions, open-loop or closed-loop, round-robin over the senders, endpoints
+    /// and methods. Transaction `i` pays `tips[i]` on top of the base fee if given, and the
+    /// submitter's fees otherwise.
+    async fn send_batch(&self, count: u64, tips: &[U256]) -> Vec<TxResult> {
+        match self.rate {
+            Some(rate) => self.run_open_loop(rate, count, tips).await,
+            None => self.run_closed_loop(count, tips).await,
+        }
+    }
+
+    /// Sender that transaction `index` of a batch is sent from, the endpoint it is sent to, and the
+    /// endpoint's submitter for that sender and method. Consecutive transactions come from
+    /// consecutive senders, each round over the senders moves on to the next endpoint, and each
+    /// round over the endpoints to the next method, so every sender and endpoint sees every method.
+    fn lane_for(&self, index: u64) -> (&Arc<Sender>, &Arc<Endpoint>, usize) {
+        let senders = self.senders.len() as u64;
+        let endpoints = self.endpoints.len() as u64;
+        let sender = (index % senders) as usize;
+        let round = index / senders;
+        let endpoint = &self.endpoints[(round % endpoints) as usize];
+        let method = ((round / endpoints) % self.methods.len() as u64) as usize;
+        (&self.senders[sender], endpoint, sender * self.methods.len() + method)
+    }
+
+    /// Closed-loop run: keeps up to `concurrency` transactions in flight and starts the next one
+    /// only when a previous one completes
+    async fn run_closed_loop(&self, count: u64, tips: &[U256]) -> Vec<TxResult> {
+        // Nonces are assigned in submission order and new transactions are started in order, but
+        // concurrent sends race each other to the node, so it may receive a nonce before the one
+        // below it and queue it until the gap closes
+        stream::iter(0..count)
+            .map(|i| {
+                let (sender, endpoint, submitter) = self.lane_for(i);
+                let nonce = sender.nonces.reserve();
+                let tip = tips.get(i as usize).copied();
+                run_transaction(endpoint, submitter, sender, self.head_tracker.as_deref(), i, nonce, tip, Instant::now())
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await
+    }
+
+    /// Open-loop run: starts transaction `i` at `start + i / rate` no matter how many are still
+    /// in flight, so a slow endpoint cannot hold back the send schedule
+    async fn run_open_loop(&self, rate: f64, count: u64, tips: &[U256]) -> Vec<TxResult> {
+        let interval = Duration::from_secs_f64(1.0 / rate);
+        let start = Instant::now();
+        let mut handles = Vec::with_capacity(count as usize);
+
+        for i in 0..count {
+            let intended_start = start + interval.mul_f64(i as f64);
+            tokio::time::sleep_until(intended_start.into()).await;
+
+            let (sender, endpoint, submitter) = self.lane_for(i);
+            let (sender, endpoint) = (sender.clone(), endpoint.clone());
+            let head_tracker = self.head_tracker.clone();
+            let nonce = sender.nonces.reserve();
+            let tip = tips.get(i as usize).copied();
+            handles.push(tokio::spawn(async move {
+                run_transaction(&endpoint, submitter, &sender, head_tracker.as_deref(), i, nonce, tip, intended_start).await
+            }));
+        }
+
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            match handle.await {
+                Ok(result) => results.push(result),
+                Err(e) => warn!("Transaction task failed: {}", e),
+            }
+        }
+        results
+    }
+}
+
+/// Runs one benchmarked transaction and measures its latency.
+/// Total time is measured from `intended_start` rather than the actual start, so that any delay
+/// in getting the transaction out is charged to it (coordinated omission correction).
+/// Failures are recorded in the result rather than returned, so they can be exported.
+/// A `tip` replaces the submitter's fees with that priority fee on top of the latest base fee.
+#[allow(clippy::too_many_arguments)]
+async fn run_transaction(
+    endpoint: &Endpoint,
+    submitter: usize,
+    sender: &Sender,
+    head_tracker: Option<&HeadTracker>,
+    index: u64,
+    nonce: u64,
+    tip: Option<U256>,
+    intended_start: Instant,
+) -> TxResult {
+    let submitter = &endpoint.submitters[submitter];
+    let wallet = submitter.client().address();
+    info!("\n--- Transaction #{} (wallet: {:?}, nonce: {}, endpoint: {}, method: {}) ---",
+          index + 1, wallet, nonce, endpoint.label, submitter.method());
+
+    // Start timing total transaction time
+    let tx_start = Instant::now();
+    let start_lag = tx_start.saturating_duration_since(intended_start);
+
+    let gas_strategy = match tip {
+        Some(priority_fee) => GasStrategy::BaseFeePlus { priority_fee },
+        None => submitter.gas_strategy(),
+    };
+    let (outcome, http_calls) = record_http_timings(submitter.submit_with(index, nonce, &gas_strategy)).await;
+
+    let total_time = intended_start.elapsed();
+    let mut result = TxResult {
+        index,
+        wallet,
+        nonce,
+        endpoint: endpoint.label.clone(),
+        method: submitter.method(),
+        tx_type: submitter.tx_type(),
+        hash: None,
+        send_time: Duration::default(),
+        confirm_time: Duration::default(),
+        phases: PhaseTimings::default(),
+        total_time,
+        start_lag,
+        block_number: None,
+        gas_used: None,
+        block_timing: None,
+        reorgs: 0,
+        final_block_number: None,
+        reorg_delay: None,
+        tracking_timed_out: false,
+        fee_per_gas: None,
+        priority_fee: None,
+        tip_level: tip,
+        replacements: Vec::new(),
+        receipt_calls: None,
+        http_calls,
+        observations: Vec::new(),
+        warmup: false,
+        status: TxStatus::Error,
+        error: None,
+    };
+
+    match outcome {
+        Ok(submission) => {
+            info!("TX #{}: total time: {:?} (send: {:?}, confirm: {:?})",
+                  index + 1, total_time, submission.send_time, submission.confirm_time);
+            result.hash = Some(submission.receipt.transaction_hash);
+            result.send_time = submission.send_time;
+            result.confirm_time = submission.confirm_time;
+            result.phases = submission.phases;
+            result.block_number = submission.receipt.block_number.map(|b| b.as_u64());
+            result.gas_used = submission.receipt.gas_used;
+            result.receipt_calls = submission.receipt_calls;
+            result.fee_per_gas = Some(submission.fee_per_gas);
+            result.priority_fee = submission.priority_fee;
+            result.replacements = submission.replacements;
+            result.status = if submission.cancelled {
+                TxStatus::Cancelled
+            } else {
+                TxStatus::from_receipt(&submission.receipt)
+            };
+            if result.status.is_included() && submitter.confirmation_target().is_beyond_receipt() {
+                match submitter.track_confirmations(&submission.receipt).await {
+                    Ok(outcome) => {
+                        let to_receipt = result.phases.to_receipt;
+                        result.phases.to_confirmations = outcome.to_confirmations.map(|d| to_receipt + d);
+                        result.phases.to_finality = outcome.to_finality.map(|d| to_receipt + d);
+                        result.reorgs = outcome.reorgs;
+                        result.final_block_number = outcome.final_block.map(|(number, _)| number);
+                        result.reorg_delay = outcome.reincluded;
+                        result.tracking_timed_out = outcome.timed_out;
+                    }
+                    Err(e) => warn!("TX #{}: confirmation tracking failed: {}", index + 1, e),
+                }
+            }
+            if let (Some(head_tracker), Some(block_number)) = (head_tracker, result.block_number) {
+                result.block_timing = block_timing(head_tracker, block_number, submission.sent_at).await;
+            }
+            // Observers may lag behind the submission endpoint, so wait for them outside the measured time
+            result.observations = submission.observations.collect().await;
+        }
+        Err(e) => {
+            warn!("TX #{}: error: {}", index + 1, e);
+            result.error = Some(e.to_string());
+            // A nonce left unused would stall every later transaction from the same wallet
+            nonce::recover(submitter.client(), &sender.nonces, nonce, &e.to_string(), &submitter.gas_strategy()).await;
+        }
+    }
+    info!("--- End Transaction #{} ---\n", index + 1);
+
+    result
+}
+
+/// Compares the including block's timestamp with the local send time, and looks up the head the
+/// transaction was sent at. Fetching the header happens after the transaction's latency was measured.
+async fn block_timing(head_tracker: &HeadTracker, block_number: u64, sent_at: SystemTime) -> Option<BlockTiming> {
+    match head_tracker.block_timestamp(block_number).await {
+        Ok(block_timestamp) => Some(BlockTiming {
+            block_timestamp,
+            block_minus_send_ms: block_timestamp as f64 * 1000.0 - millis_since_epoch(sent_at),
+            head_at_send: head_tracker.head_at(sent_at),
+        }),
+        Err(e) => {
+            warn!("Failed to fetch block {}: {}", block_number, e);
+            None
+        }
+    }
+}
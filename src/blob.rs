@@ -0,0 +1,185 @@
+use anyhow::{anyhow, Result};
+use c_kzg::{Blob, KzgSettings, BYTES_PER_BLOB, BYTES_PER_FIELD_ELEMENT};
+use clap::ValueEnum;
+use ethers::{
+    types::{transaction::eip1559::Eip1559TransactionRequest, Bytes, NameOrAddress, Signature, H256, U256},
+    utils::{keccak256, rlp::RlpStream},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// EIP-2718 type byte of blob transactions
+const BLOB_TX_TYPE: u8 = 0x03;
+
+/// Blob gas used by every blob, whatever its content
+pub const BLOB_GAS_PER_BLOB: u64 = 131_072;
+
+/// Version byte of a KZG commitment's versioned hash
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Network wrapper version of a sidecar carrying cell proofs (EIP-7594)
+const CELL_PROOFS_WRAPPER_VERSION: u8 = 1;
+
+/// Content written into every field element of the generated blob
+const BLOB_FILLER: &[u8] = b"tx-latency blob ";
+
+/// Which KZG proofs the blob sidecar carries; nodes accept only the kind of their fork
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlobProofs {
+    /// One proof per blob (EIP-4844), for chains before the Osaka upgrade
+    #[default]
+    Blob,
+    /// One proof per cell of the extended blob (EIP-7594), for chains from Osaka on
+    Cell,
+}
+
+impl std::fmt::Display for BlobProofs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlobProofs::Blob => write!(f, "blob"),
+            BlobProofs::Cell => write!(f, "cell"),
+        }
+    }
+}
+
+/// A single blob with its KZG commitment and proofs, generated locally. Every blob transaction of
+/// a run carries the same one, so the proofs are only computed once.
+pub struct BlobSidecar {
+    blob: Box<[u8; BYTES_PER_BLOB]>,
+    commitment: [u8; 48],
+    proofs: Vec<[u8; 48]>,
+    kind: BlobProofs,
+    versioned_hash: H256,
+}
+
+impl BlobSidecar {
+    /// Builds a blob of filler bytes and computes its commitment and `kind` of proofs with the
+    /// Ethereum mainnet trusted setup bundled with c-kzg
+    pub fn generate(kind: BlobProofs) -> Result<Self> {
+        let mut bytes = vec![0u8; BYTES_PER_BLOB];
+        // The first byte of every field element stays zero, keeping it below the BLS modulus
+        for element in bytes.chunks_mut(BYTES_PER_FIELD_ELEMENT) {
+            for (byte, filler) in element[1..].iter_mut().zip(BLOB_FILLER.iter().cycle()) {
+                *byte = *filler;
+            }
+        }
+        let blob = Blob::from_bytes(&bytes)?;
+
+        let settings: &KzgSettings = c_kzg::ethereum_kzg_settings(0);
+        let commitment = settings.blob_to_kzg_commitment(&blob)?;
+        let proofs = match kind {
+            BlobProofs::Blob => vec![*settings.compute_blob_kzg_proof(&blob, &commitment.to_bytes())?],
+            BlobProofs::Cell => settings.compute_cells_and_kzg_proofs(&blob)?.1.iter().map(|proof| **proof).collect(),
+        };
+
+        let mut versioned_hash: [u8; 32] = Sha256::digest(*commitment).into();
+        versioned_hash[0] = VERSIONED_HASH_VERSION_KZG;
+        Ok(Self {
+            blob: Box::new(blob.into_inner()),
+            commitment: *commitment,
+            proofs,
+            kind,
+            versioned_hash: H256(versioned_hash),
+        })
+    }
+
+    pub fn kind(&self) -> BlobProofs {
+        self.kind
+    }
+}
+
+impl std::fmt::Debug for BlobSidecar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlobSidecar")
+            .field("kind", &self.kind)
+            .field("versioned_hash", &self.versioned_hash)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An EIP-4844 transaction: the fields of an EIP-1559 transaction plus a blob fee cap and the
+/// versioned hashes of its blobs. ethers has no type-3 support, so it is signed and encoded here.
+#[derive(Debug)]
+pub struct BlobTransaction<'a> {
+    tx: &'a Eip1559TransactionRequest,
+    max_fee_per_blob_gas: U256,
+    sidecar: &'a BlobSidecar,
+}
+
+impl<'a> BlobTransaction<'a> {
+    pub fn new(tx: &'a Eip1559TransactionRequest, max_fee_per_blob_gas: U256, sidecar: &'a BlobSidecar) -> Self {
+        Self { tx, max_fee_per_blob_gas, sidecar }
+    }
+
+    /// Hash the sender signs: of the type byte and the payload without a signature
+    pub fn sighash(&self) -> Result<H256> {
+        Ok(H256(keccak256(self.encode(None)?)))
+    }
+
+    /// Encodes the signed transaction in its network form, with the sidecar, as
+    /// `eth_sendRawTransaction` expects it. Returns the encoding and the transaction hash, which
+    /// covers the signed transaction without the sidecar.
+    pub fn encode_signed(&self, signature: &Signature) -> Result<(Bytes, H256)> {
+        // Signatures of a hash carry a legacy `v` of 27 or 28
+        let y_parity = signature.v.checked_sub(27).ok_or_else(|| anyhow!("unexpected signature v {}", signature.v))?;
+        let signed = (y_parity, signature.r, signature.s);
+        let hash = H256(keccak256(self.encode(Some(signed))?));
+
+        let sidecar = self.sidecar;
+        let mut rlp = RlpStream::new();
+        match sidecar.kind {
+            BlobProofs::Blob => rlp.begin_list(4),
+            BlobProofs::Cell => rlp.begin_list(5),
+        };
+        self.append_payload(&mut rlp, Some(signed))?;
+        if sidecar.kind == BlobProofs::Cell {
+            rlp.append(&CELL_PROOFS_WRAPPER_VERSION);
+        }
+        rlp.begin_list(1).append(&sidecar.blob.as_slice());
+        rlp.begin_list(1).append(&sidecar.commitment.as_slice());
+        rlp.begin_list(sidecar.proofs.len());
+        for proof in &sidecar.proofs {
+            rlp.append(&proof.as_slice());
+        }
+
+        let mut raw_tx = vec![BLOB_TX_TYPE];
+        raw_tx.extend_from_slice(&rlp.out());
+        Ok((raw_tx.into(), hash))
+    }
+
+    /// Type byte followed by the RLP payload, signed if a signature is given
+    fn encode(&self, signature: Option<(u64, U256, U256)>) -> Result<Vec<u8>> {
+        let mut rlp = RlpStream::new();
+        self.append_payload(&mut rlp, signature)?;
+        let mut encoded = vec![BLOB_TX_TYPE];
+        encoded.extend_from_slice(&rlp.out());
+        Ok(encoded)
+    }
+
+    fn append_payload(&self, rlp: &mut RlpStream, signature: Option<(u64, U256, U256)>) -> Result<()> {
+        let tx = self.tx;
+        let to = match &tx.to {
+            Some(NameOrAddress::Address(to)) => *to,
+            _ => return Err(anyhow!("blob transactions need a recipient address; they can't deploy contracts")),
+        };
+        rlp.begin_list(if signature.is_some() { 14 } else { 11 });
+        rlp.append(&tx.chain_id.unwrap_or_default());
+        rlp.append(&tx.nonce.unwrap_or_default());
+        rlp.append(&tx.max_priority_fee_per_gas.unwrap_or_default());
+        rlp.append(&tx.max_fee_per_gas.unwrap_or_default());
+        rlp.append(&tx.gas.unwrap_or_default());
+        rlp.append(&to);
+        rlp.append(&tx.value.unwrap_or_default());
+        rlp.append(&tx.data.as_deref().unwrap_or_default());
+        rlp.append(&tx.access_list);
+        rlp.append(&self.max_fee_per_blob_gas);
+        rlp.begin_list(1).append(&self.sidecar.versioned_hash);
+        if let Some((y_parity, r, s)) = signature {
+            rlp.append(&y_parity);
+            rlp.append(&r);
+            rlp.append(&s);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::Result;
+use ethers::{
+    providers::{Middleware, Provider, StreamExt, Ws},
+    types::H256,
+};
+use log::warn;
+use tokio::sync::oneshot;
+
+use crate::subscription::{subscribed, wake, HashSubscription, Waiters};
+
+/// Follows new blocks over a WebSocket `newHeads` subscription and notifies whoever is waiting
+/// for a transaction as soon as the block containing it arrives, instead of polling for receipts
+#[derive(Debug)]
+pub struct BlockWatcher {
+    subscription: HashSubscription<u64>,
+}
+
+impl BlockWatcher {
+    /// Connects to a WebSocket endpoint and subscribes to `newHeads`
+    pub async fn connect(ws_url: &str) -> Result<Self> {
+        let provider = Provider::<Ws>::connect(ws_url).await?;
+        let subscription = HashSubscription::spawn("block", |waiters, ready| follow_heads(provider, waiters, ready)).await?;
+        Ok(Self { subscription })
+    }
+
+    /// Registers interest in `tx_hash`; the receiver yields the number of the block that includes it.
+    /// Register before checking for an existing receipt, so a block arriving in between isn't missed.
+    pub fn watch(&self, tx_hash: H256) -> oneshot::Receiver<u64> {
+        self.subscription.watch(tx_hash)
+    }
+
+    /// Stops watching `tx_hash`, e.g. because its receipt was found another way
+    pub fn unwatch(&self, tx_hash: H256) {
+        self.subscription.unwatch(tx_hash);
+    }
+
+    /// Whether the `newHeads` subscription has ended, after which receipts have to be polled
+    pub fn is_closed(&self) -> bool {
+        self.subscription.is_closed()
+    }
+}
+
+/// Subscription loop: fetches the transaction hashes of every new block and wakes their waiters
+async fn follow_heads(provider: Provider<Ws>, waiters: Waiters<u64>, ready: oneshot::Sender<Result<()>>) {
+    let Some(mut heads) = subscribed(provider.subscribe_blocks().await, ready) else {
+        return;
+    };
+
+    while let Some(head) = heads.next().await {
+        let (Some(block_hash), Some(block_number)) = (head.hash, head.number) else {
+            continue;
+        };
+        // Heads carry no transactions, so fetch the hashes of the ones in this block
+        let block = match provider.get_block(block_hash).await {
+            Ok(Some(block)) => block,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("Failed to fetch block {}: {}", block_number, e);
+                continue;
+            }
+        };
+
+        for tx_hash in &block.transactions {
+            wake(&waiters, tx_hash, block_number.as_u64());
+        }
+    }
+
+    warn!("newHeads subscription ended");
+}
@@ -0,0 +1,183 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use ethers::{
+    providers::Middleware,
+    types::{BlockNumber, TransactionReceipt, H256},
+};
+use log::{info, warn};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+use crate::submitter::PollStrategy;
+use crate::Client;
+
+/// Block tag whose coverage of the including block counts as final
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Finality {
+    /// The `safe` tag: justified by the consensus layer, unlikely to be reorged
+    Safe,
+    /// The `finalized` tag: cannot be reorged without slashing
+    Finalized,
+}
+
+impl Finality {
+    fn block_tag(&self) -> BlockNumber {
+        match self {
+            Finality::Safe => BlockNumber::Safe,
+            Finality::Finalized => BlockNumber::Finalized,
+        }
+    }
+}
+
+impl std::fmt::Display for Finality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Finality::Safe => write!(f, "safe"),
+            Finality::Finalized => write!(f, "finalized"),
+        }
+    }
+}
+
+/// How far past the first receipt a transaction is tracked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfirmationTarget {
+    /// Blocks that must exist on top of the including block, counting the block itself;
+    /// 1 is satisfied by the receipt alone
+    pub confirmations: u64,
+    pub finality: Option<Finality>,
+    /// How long to track after the first receipt before giving up on the targets
+    pub timeout: Duration,
+}
+
+impl ConfirmationTarget {
+    /// Whether there is anything to track beyond the first receipt
+    pub fn is_beyond_receipt(&self) -> bool {
+        self.confirmations > 1 || self.finality.is_some()
+    }
+}
+
+impl Default for ConfirmationTarget {
+    fn default() -> Self {
+        Self { confirmations: 1, finality: None, timeout: Duration::from_secs(20 * 60) }
+    }
+}
+
+/// What happened to an included transaction after its first receipt, with times measured from
+/// the first receipt
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConfirmationOutcome {
+    /// Set when more than one confirmation was requested
+    pub to_confirmations: Option<Duration>,
+    /// Set when a finality tag was requested
+    pub to_finality: Option<Duration>,
+    /// Times the including block was found to be no longer canonical
+    pub reorgs: u32,
+    /// Block the transaction ended up in after a reorg
+    pub final_block: Option<(u64, H256)>,
+    /// When the receipt from the block it ended up in was first seen, after the last reorg
+    pub reincluded: Option<Duration>,
+    /// Set when the timeout passed before every target was reached; the missing times stay unset
+    pub timed_out: bool,
+}
+
+/// Polls the head and the finality tag until the receipt's block is deep enough and covered by the
+/// tag. Every poll also checks that the including block is still canonical; if it was reorged out,
+/// the transaction is followed into the block it lands in instead and the targets start over.
+/// RPC errors are retried, since giving up would lose an otherwise good measurement, until the
+/// target's timeout passes; the outcome then has what was reached so far and is marked timed out.
+pub async fn track(
+    client: &Client,
+    receipt: &TransactionReceipt,
+    target: ConfirmationTarget,
+    poll_strategy: PollStrategy,
+) -> Result<ConfirmationOutcome> {
+    let (Some(block_number), Some(block_hash)) = (receipt.block_number, receipt.block_hash) else {
+        return Err(anyhow!("receipt has no block"));
+    };
+    let tx_hash = receipt.transaction_hash;
+    let mut block = Some((block_number.as_u64(), block_hash));
+    // Hash of the block the transaction was last reorged out of, whose receipt a lagging node may still return
+    let mut stale_hash = None;
+    let start = Instant::now();
+    let mut outcome = ConfirmationOutcome::default();
+    let mut confirmed = target.confirmations <= 1;
+    let mut finalized = target.finality.is_none();
+    let mut polls = 0;
+
+    while !(confirmed && finalized) {
+        polls += 1;
+
+        match block {
+            // Reorged out: wait for the receipt from the block the transaction lands in next
+            None => match client.get_transaction_receipt(tx_hash).await {
+                Ok(Some(r)) => {
+                    if let (Some(number), Some(hash)) = (r.block_number, r.block_hash)
+                        && Some(hash) != stale_hash
+                    {
+                        info!("Re-included in block {} after {:?}", number, start.elapsed());
+                        block = Some((number.as_u64(), hash));
+                        outcome.final_block = block;
+                        outcome.reincluded = Some(start.elapsed());
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Failed to fetch the receipt: {}", e),
+            },
+            Some((number, hash)) => match client.get_block(number).await {
+                Ok(Some(canonical)) if canonical.hash.is_some_and(|h| h != hash) => {
+                    warn!("Block {} containing {:?} was reorged out", number, tx_hash);
+                    outcome.reorgs += 1;
+                    stale_hash = Some(hash);
+                    block = None;
+                    confirmed = target.confirmations <= 1;
+                    finalized = target.finality.is_none();
+                    outcome.to_confirmations = None;
+                    outcome.to_finality = None;
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to fetch block {}: {}", number, e),
+            },
+        }
+
+        if let Some((number, _)) = block {
+            if !confirmed {
+                match client.get_block_number().await {
+                    Ok(head) if head.as_u64() + 1 >= number + target.confirmations => {
+                        confirmed = true;
+                        outcome.to_confirmations = Some(start.elapsed());
+                        info!("{} confirmations after {:?}", target.confirmations, start.elapsed());
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Failed to fetch the block number: {}", e),
+                }
+            }
+            if let Some(finality) = target.finality.filter(|_| !finalized) {
+                match client.get_block(finality.block_tag()).await {
+                    Ok(Some(tagged)) if tagged.number.is_some_and(|n| n.as_u64() >= number) => {
+                        finalized = true;
+                        outcome.to_finality = Some(start.elapsed());
+                        info!("Block {} is {} after {:?}", number, finality, start.elapsed());
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Failed to fetch the {} block: {}", finality, e),
+                }
+            }
+        }
+
+        if !(confirmed && finalized) {
+            if start.elapsed() >= target.timeout {
+                warn!("Gave up tracking {:?} after {:?} without reaching the confirmation target", tx_hash, target.timeout);
+                outcome.timed_out = true;
+                break;
+            }
+            let delay = poll_strategy.delay(polls);
+            if !delay.is_zero() {
+                sleep(delay).await;
+            }
+        }
+    }
+
+    Ok(outcome)
+}
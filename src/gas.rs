@@ -0,0 +1,159 @@
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{BlockNumber, U256},
+};
+
+use crate::Client;
+
+/// Priority fee used with a gas price when none is given: 1 gwei
+pub const DEFAULT_PRIORITY_FEE: u64 = 1_000_000_000;
+
+/// Gas price used when `eth_gasPrice` returns zero, as some dev chains do: 1 gwei
+const ZERO_GAS_PRICE_FALLBACK: u64 = 1_000_000_000;
+
+/// How the fees of a transaction are chosen. Every strategy except [`GasStrategy::Fixed`] asks the
+/// node, so its fees follow the chain when evaluated per transaction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GasStrategy {
+    /// The same gas price and priority fee for every transaction
+    Fixed { gas_price: U256, priority_fee: U256 },
+    /// `eth_gasPrice` times `multiplier`, with a fixed priority fee
+    Multiplier { multiplier: f64, priority_fee: U256 },
+    /// Priority fee at `percentile` of the rewards paid in the last `blocks` blocks, averaged over
+    /// the blocks, on top of the next block's base fee (`eth_feeHistory`)
+    FeeHistory { blocks: u64, percentile: f64 },
+    /// Priority fee suggested by `eth_maxPriorityFeePerGas`, on top of the latest base fee
+    NodeSuggested,
+    /// A fixed priority fee on top of the latest base fee, so only the tip is under control
+    BaseFeePlus { priority_fee: U256 },
+}
+
+impl Default for GasStrategy {
+    /// 3x `eth_gasPrice` with a 1 gwei priority fee
+    fn default() -> Self {
+        GasStrategy::Multiplier { multiplier: 3.0, priority_fee: U256::from(DEFAULT_PRIORITY_FEE) }
+    }
+}
+
+/// Fees for each transaction type, as chosen by a [`GasStrategy`]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Fees {
+    /// Gas price of legacy and EIP-2930 transactions
+    pub gas_price: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+impl Fees {
+    /// Fees around a single gas price: it is also the max fee, unless it doesn't cover the priority
+    /// fee, in which case the max fee is twice the priority fee
+    fn from_gas_price(gas_price: U256, priority_fee: U256) -> Self {
+        let max_fee_per_gas = if gas_price > priority_fee { gas_price } else { priority_fee * 2 };
+        Self { gas_price, max_fee_per_gas, max_priority_fee_per_gas: priority_fee }
+    }
+
+    /// Fees on top of a base fee: legacy transactions pay the base fee plus the priority fee, and
+    /// the max fee leaves room for the base fee to double before inclusion
+    fn from_base_fee(base_fee: U256, priority_fee: U256) -> Self {
+        Self {
+            gas_price: base_fee + priority_fee,
+            max_fee_per_gas: base_fee * 2 + priority_fee,
+            max_priority_fee_per_gas: priority_fee,
+        }
+    }
+}
+
+impl GasStrategy {
+    /// Whether the fees are the same every time, without asking the node
+    pub fn is_fixed(&self) -> bool {
+        matches!(self, GasStrategy::Fixed { .. })
+    }
+
+    /// Evaluates the strategy against the node's current fee market
+    pub async fn fees(&self, client: &Client) -> Result<Fees> {
+        match *self {
+            GasStrategy::Fixed { gas_price, priority_fee } => Ok(Fees::from_gas_price(gas_price, priority_fee)),
+            GasStrategy::Multiplier { multiplier, priority_fee } => {
+                let gas_price = client.get_gas_price().await?;
+                let gas_price = if gas_price.is_zero() {
+                    U256::from(ZERO_GAS_PRICE_FALLBACK)
+                } else {
+                    // Scale in thousandths, since U256 has no floating-point multiplication
+                    gas_price * U256::from((multiplier * 1000.0).round() as u64) / 1000
+                };
+                Ok(Fees::from_gas_price(gas_price, priority_fee))
+            }
+            GasStrategy::FeeHistory { blocks, percentile } => {
+                let history = client.fee_history(blocks, BlockNumber::Latest, &[percentile]).await?;
+                // The last entry is the base fee of the block after the newest one
+                let base_fee = *history
+                    .base_fee_per_gas
+                    .last()
+                    .ok_or_else(|| anyhow!("eth_feeHistory returned no base fee; the chain may not support EIP-1559"))?;
+                let rewards: Vec<U256> = history.reward.iter().filter_map(|r| r.first().copied()).collect();
+                let priority_fee = match rewards.len() {
+                    0 => U256::zero(),
+                    n => rewards.iter().fold(U256::zero(), |sum, r| sum + r) / n,
+                };
+                Ok(Fees::from_base_fee(base_fee, priority_fee))
+            }
+            GasStrategy::NodeSuggested => {
+                let (priority_fee, base_fee) = tokio::try_join!(
+                    async { Ok(client.provider().request::<_, U256>("eth_maxPriorityFeePerGas", ()).await?) },
+                    latest_base_fee(client),
+                )?;
+                Ok(Fees::from_base_fee(base_fee, priority_fee))
+            }
+            GasStrategy::BaseFeePlus { priority_fee } => {
+                Ok(Fees::from_base_fee(latest_base_fee(client).await?, priority_fee))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for GasStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GasStrategy::Fixed { gas_price, priority_fee } => {
+                write!(f, "fixed {} (priority fee {})", fmt_gwei(*gas_price), fmt_gwei(*priority_fee))
+            }
+            GasStrategy::Multiplier { multiplier, priority_fee } => {
+                write!(f, "{}x eth_gasPrice (priority fee {})", multiplier, fmt_gwei(*priority_fee))
+            }
+            GasStrategy::FeeHistory { blocks, percentile } => {
+                write!(f, "eth_feeHistory p{} over {} blocks", percentile, blocks)
+            }
+            GasStrategy::NodeSuggested => write!(f, "eth_maxPriorityFeePerGas"),
+            GasStrategy::BaseFeePlus { priority_fee } => write!(f, "latest base fee + {} tip", fmt_gwei(*priority_fee)),
+        }
+    }
+}
+
+/// Blob fee cap of a blob transaction: twice the current blob base fee (`eth_blobBaseFee`), which
+/// leaves room for it to double before inclusion, as the max fee does for the base fee
+pub async fn max_fee_per_blob_gas(client: &Client) -> Result<U256> {
+    let blob_base_fee: U256 = client.provider().request("eth_blobBaseFee", ()).await?;
+    Ok((blob_base_fee * 2u64).max(U256::one()))
+}
+
+/// Base fee of the latest block
+async fn latest_base_fee(client: &Client) -> Result<U256> {
+    client
+        .get_block(BlockNumber::Latest)
+        .await?
+        .and_then(|b| b.base_fee_per_gas)
+        .ok_or_else(|| anyhow!("the latest block has no base fee; the chain may not support EIP-1559"))
+}
+
+/// Formats a fee in wei as gwei, e.g. `1.5 gwei`
+pub fn fmt_gwei(wei: U256) -> String {
+    let gwei = U256::exp10(9);
+    let (whole, rest) = wei.div_mod(gwei);
+    if rest.is_zero() {
+        format!("{} gwei", whole)
+    } else {
+        let fraction = format!("{:09}", rest.as_u64());
+        format!("{}.{} gwei", whole, fraction.trim_end_matches('0'))
+    }
+}
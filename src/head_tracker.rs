@@ -0,0 +1,128 @@
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::BlockNumber,
+};
+use log::warn;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{task::JoinHandle, time::sleep};
+
+use crate::Client;
+
+#[derive(Debug, Default)]
+struct Heads {
+    /// Block numbers in the order they became the head, with the local time they were first seen
+    history: Vec<(SystemTime, u64)>,
+    /// Timestamps of the blocks seen so far, in seconds
+    timestamps: HashMap<u64, u64>,
+    /// Local time a new head was seen minus its timestamp, in milliseconds
+    offset_samples: Vec<f64>,
+}
+
+/// Polls the latest block in the background to know the chain head at any moment and to estimate
+/// the offset between the local clock and block timestamps
+#[derive(Debug)]
+pub struct HeadTracker {
+    client: Arc<Client>,
+    heads: Arc<Mutex<Heads>>,
+    task: JoinHandle<()>,
+}
+
+impl HeadTracker {
+    /// Fetches the current head, then keeps polling `eth_getBlockByNumber("latest")` every `interval`
+    pub async fn start(client: Arc<Client>, interval: Duration) -> Result<Self> {
+        let heads = Arc::new(Mutex::new(Heads::default()));
+        // The first head must be known before any transaction is sent
+        poll_head(&client, &heads).await?;
+        let task = tokio::spawn(poll_heads(client.clone(), heads.clone(), interval));
+        Ok(Self { client, heads, task })
+    }
+
+    /// The head block as last seen at or before `at`, or `None` if no block had been seen yet
+    pub fn head_at(&self, at: SystemTime) -> Option<u64> {
+        let heads = self.heads.lock().unwrap();
+        heads
+            .history
+            .iter()
+            .take_while(|(seen, _)| *seen <= at)
+            .last()
+            .map(|&(_, number)| number)
+    }
+
+    /// Timestamp of block `number` in seconds, fetched unless the tracker already saw the block
+    pub async fn block_timestamp(&self, number: u64) -> Result<u64> {
+        if let Some(&timestamp) = self.heads.lock().unwrap().timestamps.get(&number) {
+            return Ok(timestamp);
+        }
+        let block = self
+            .client
+            .get_block(number)
+            .await?
+            .ok_or_else(|| anyhow!("block {} not found", number))?;
+        let timestamp = block.timestamp.as_u64();
+        self.heads.lock().unwrap().timestamps.insert(number, timestamp);
+        Ok(timestamp)
+    }
+
+    /// Estimated offset of the local clock from block timestamps (local minus chain), in milliseconds,
+    /// and the number of blocks it was estimated from.
+    ///
+    /// A block can only be seen after its timestamp, so the smallest "seen minus timestamp" over all
+    /// new heads is the closest to the true offset. It still includes the fastest delivery of a block
+    /// to this client, and block timestamps usually have one-second resolution.
+    pub fn clock_offset(&self) -> Option<(f64, usize)> {
+        let heads = self.heads.lock().unwrap();
+        let offset = heads.offset_samples.iter().copied().reduce(f64::min)?;
+        Some((offset, heads.offset_samples.len()))
+    }
+}
+
+impl Drop for HeadTracker {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Polling loop: records every new head with the time it was first seen
+async fn poll_heads(client: Arc<Client>, heads: Arc<Mutex<Heads>>, interval: Duration) {
+    loop {
+        sleep(interval).await;
+        if let Err(e) = poll_head(&client, &heads).await {
+            warn!("Failed to fetch the latest block: {}", e);
+        }
+    }
+}
+
+/// Fetches the latest block and records it if it is a new head
+async fn poll_head(client: &Client, heads: &Mutex<Heads>) -> Result<()> {
+    let block = client
+        .get_block(BlockNumber::Latest)
+        .await?
+        .ok_or_else(|| anyhow!("no latest block"))?;
+    let seen = SystemTime::now();
+    let number = block.number.map(|n| n.as_u64()).ok_or_else(|| anyhow!("latest block has no number"))?;
+    let timestamp = block.timestamp.as_u64();
+
+    let mut heads = heads.lock().unwrap();
+    heads.timestamps.insert(number, timestamp);
+    match heads.history.last() {
+        Some(&(_, last)) if number <= last => {}
+        previous => {
+            // The first poll only tells us the block exists, not when it arrived
+            if previous.is_some() {
+                heads.offset_samples.push(millis_since_epoch(seen) - timestamp as f64 * 1000.0);
+            }
+            heads.history.push((seen, number));
+        }
+    }
+    Ok(())
+}
+
+/// Milliseconds since the Unix epoch, with sub-millisecond precision
+pub fn millis_since_epoch(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64() * 1000.0
+}
@@ -0,0 +1,60 @@
+//! Transaction latency benchmarking for EVM blockchains.
+//!
+//! The `tx-latency` binary is a thin CLI over this crate; harnesses can drive the same
+//! benchmark directly:
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use tx_latency::{Benchmark, TxMethod};
+//!
+//! let benchmark = Benchmark::builder()
+//!     .rpc_url("https://your-rpc-endpoint.com")
+//!     .private_key("0x...")
+//!     .method(TxMethod::Rise)
+//!     .num_transactions(20)
+//!     .build()
+//!     .await?;
+//! let report = benchmark.run().await;
+//! println!("p99: {:?}", report.total_stats().map(|s| s.percentile(0.99)));
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Progress for each transaction is emitted through the `log` crate at `info` level.
+
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::Provider,
+    signers::LocalWallet,
+};
+
+pub mod benchmark;
+pub mod blob;
+pub mod block_watcher;
+pub mod confirmation;
+pub mod export;
+pub mod gas;
+pub mod head_tracker;
+pub mod middleware;
+pub mod nonce;
+pub mod observer;
+pub mod probe;
+pub mod replacement;
+pub mod report;
+pub mod stats;
+pub mod submitter;
+pub mod subscription;
+pub mod transport;
+pub mod wallets;
+pub mod workload;
+
+pub use benchmark::{Benchmark, BenchmarkBuilder, Endpoint};
+pub use blob::BlobProofs;
+pub use gas::GasStrategy;
+pub use replacement::{StuckAction, StuckPolicy};
+pub use report::{BlockTiming, PhaseTimings, Report, TxResult, TxStatus};
+pub use submitter::{PollStrategy, Submission, Submitter, TxMethod, TxType};
+pub use workload::Workload;
+
+/// Signing client used for all benchmark transactions
+pub type Client = SignerMiddleware<Provider<transport::Transport>, LocalWallet>;
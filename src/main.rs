@@ -1,32 +1,14 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use clap::{Parser, ValueEnum};
 use dotenv::dotenv;
-use ethers::{
-    middleware::SignerMiddleware,
-    providers::{Http, Middleware, Provider},
-    signers::{LocalWallet, Signer},
-    types::{transaction::eip2718::TypedTransaction, TransactionReceipt, H256, U256},
-};
-use std::{sync::Arc, time::Instant};
-use tokio::time::sleep;
+use simplelog::{ConfigBuilder, LevelFilter, SimpleLogger};
+use std::path::PathBuf;
 use std::time::Duration;
-
-#[derive(Debug, Clone, Copy, ValueEnum)]
-enum TxMethod {
-    Async,
-    Rise,
-    Mega,
-}
-
-impl std::fmt::Display for TxMethod {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            TxMethod::Async => write!(f, "async"),
-            TxMethod::Rise => write!(f, "rise"),
-            TxMethod::Mega => write!(f, "mega"),
-        }
-    }
-}
+use ethers::types::{Address, U256};
+use tx_latency::{
+    confirmation::Finality, export::OutputFormat, gas::fmt_gwei, Benchmark, BlobProofs, GasStrategy, PollStrategy, StuckAction,
+    StuckPolicy, TxMethod, TxType, Workload,
+};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -35,108 +17,407 @@ impl std::fmt::Display for TxMethod {
     version
 )]
 struct Args {
-    /// Transaction method (async, rise, mega)
-    #[arg(long, short, value_enum, default_value_t = TxMethod::Async)]
-    type_: TxMethod,
+    /// Transaction method (auto, all, async, rise, mega)
+    #[arg(long, short, value_enum, default_value_t = MethodArg::Async)]
+    type_: MethodArg,
+
+    /// Transaction type for every method (legacy, 2930, 1559, 4844). The async method used to send
+    /// legacy transactions; pass `legacy` to compare its results with runs made before this option
+    #[arg(long, value_enum, default_value_t = TxType::Eip1559)]
+    tx_type: TxType,
+
+    /// KZG proofs in the sidecar of 4844 transactions: blob (one per blob, before Osaka) or cell
+    /// (one per cell, from Osaka on)
+    #[arg(long, value_enum, default_value_t = BlobProofs::Blob)]
+    blob_proofs: BlobProofs,
 
     /// Number of transactions to send
     #[arg(long, short, default_value_t = 10)]
     num: u64,
 
-    /// RPC endpoint URL
-    #[arg(long, env = "RPC_PROVIDER")]
-    rpc: Option<String>,
+    /// Transactions to send first and exclude from statistics, to absorb connection setup and cold caches
+    #[arg(long, default_value_t = 0)]
+    warmup: u64,
+
+    /// Number of transactions to keep in flight at once (1 = sequential)
+    #[arg(long, short, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "rate")]
+    concurrency: u64,
+
+    /// Open-loop mode: send at a fixed rate regardless of completions (e.g. 50/s, 600/m)
+    #[arg(long, value_parser = parse_rate)]
+    rate: Option<f64>,
+
+    /// How long to run in open-loop mode (e.g. 60s, 500ms, 2m); defaults to --num transactions
+    #[arg(long, value_parser = parse_duration, requires = "rate")]
+    duration: Option<Duration>,
+
+    /// Export every transaction record and run metadata in a machine-readable format
+    #[arg(long, value_enum)]
+    output: Option<OutputFormat>,
+
+    /// File to write the export to (defaults to tx-latency-<timestamp>.<format>)
+    #[arg(long, requires = "output")]
+    out_file: Option<PathBuf>,
+
+    /// How the async method polls for receipts: fixed interval, exponential backoff, or hot (no sleep)
+    #[arg(long, value_enum, default_value_t = PollMode::Fixed)]
+    poll: PollMode,
+
+    /// Interval between receipt polls, or the first interval with --poll backoff (e.g. 100ms, 20ms)
+    #[arg(long, value_parser = parse_duration, default_value = "100ms")]
+    poll_interval: Duration,
+
+    /// Act on async transactions that have no receipt after this long (e.g. 30s), instead of waiting indefinitely
+    #[arg(long, value_parser = parse_duration)]
+    stuck_timeout: Option<Duration>,
+
+    /// What to do with a stuck transaction: send it again, replace it at higher fees, or replace it with a zero-value self-transfer
+    #[arg(long, value_enum, default_value_t = StuckAction::SpeedUp, requires = "stuck_timeout")]
+    stuck_action: StuckAction,
+
+    /// Fee increase of every speed-up or cancellation, in percent
+    #[arg(long, default_value_t = 10, requires = "stuck_timeout")]
+    fee_bump: u64,
+
+    /// Replacements to send for a stuck transaction before giving up on it
+    #[arg(long, default_value_t = 3, requires = "stuck_timeout")]
+    max_replacements: u32,
+
+    /// Time DNS, TCP connect, TLS handshake, write and TTFB of every JSON-RPC call, and flag reused connections
+    #[arg(long)]
+    http_timing: bool,
+
+    /// WebSocket endpoint for detecting async confirmations from a newHeads subscription instead of polling
+    #[arg(long)]
+    ws: Option<String>,
+
+    /// Endpoint to watch for every transaction to measure propagation (HTTP is polled, ws:// subscribes to pending transactions); repeatable
+    #[arg(long, value_delimiter = ',')]
+    observer: Vec<String>,
+
+    /// How often observers are polled for the transaction and its receipt
+    #[arg(long, value_parser = parse_duration, default_value = "50ms")]
+    observe_interval: Duration,
+
+    /// How long to watch observers for a transaction before counting it as not seen
+    #[arg(long, value_parser = parse_duration, default_value = "30s")]
+    observe_timeout: Duration,
+
+    /// Compare including blocks' timestamps with send times, estimate the clock offset from the chain, and count blocks from the head at send to inclusion
+    #[arg(long)]
+    block_timing: bool,
+
+    /// How often the chain head is polled with --block-timing
+    #[arg(long, value_parser = parse_duration, default_value = "100ms", requires = "block_timing")]
+    head_interval: Duration,
+
+    /// Keep tracking each transaction until its block has N confirmations (1 = first receipt)
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    confirmations: u64,
+
+    /// Keep tracking each transaction until its block is covered by the safe or finalized tag
+    #[arg(long, value_enum)]
+    finality: Option<Finality>,
+
+    /// Give up tracking --confirmations or --finality this long after the first receipt (e.g. 5m)
+    #[arg(long, value_parser = parse_duration, default_value = "20m")]
+    confirmation_timeout: Duration,
+
+    /// What every transaction does
+    #[arg(long, value_enum, default_value_t = WorkloadArg::SelfTransfer)]
+    workload: WorkloadArg,
+
+    /// Recipient of --workload transfer or erc20, or the contract called by --workload call
+    #[arg(long)]
+    to: Option<Address>,
+
+    /// Wei sent with --workload transfer or call
+    #[arg(long, value_parser = parse_wei, default_value = "0")]
+    value: U256,
+
+    /// ERC-20 token contract for --workload erc20
+    #[arg(long)]
+    token: Option<Address>,
+
+    /// Token amount in base units for --workload erc20
+    #[arg(long, value_parser = parse_wei, default_value = "1")]
+    amount: U256,
+
+    /// File with hex calldata for --workload call
+    #[arg(long)]
+    calldata_file: Option<PathBuf>,
+
+    /// File with hex init code for --workload deploy (defaults to a small gas burner contract)
+    #[arg(long)]
+    init_code_file: Option<PathBuf>,
+
+    /// Gas each --workload burn call burns, which is also its gas limit
+    #[arg(long, default_value_t = 100_000)]
+    burn_gas: u64,
+
+    /// Gas burner to call with --workload burn, instead of deploying one at startup
+    #[arg(long)]
+    burn_contract: Option<Address>,
+
+    /// Fixed gas limit for every transaction instead of eth_estimateGas (self-transfers default to 21000)
+    #[arg(long)]
+    gas_limit: Option<u64>,
+
+    /// How fees are chosen, re-evaluated for every transaction (defaults to 3x eth_gasPrice, evaluated once at startup)
+    #[arg(long, value_enum)]
+    gas_strategy: Option<GasMode>,
+
+    /// Gas price in gwei for --gas-strategy fixed (e.g. 1.5)
+    #[arg(long, value_parser = parse_gwei, required_if_eq("gas_strategy", "fixed"))]
+    gas_price: Option<U256>,
+
+    /// Multiple of eth_gasPrice for --gas-strategy multiplier
+    #[arg(long, default_value_t = 3.0)]
+    gas_multiplier: f64,
+
+    /// Priority fee in gwei for --gas-strategy fixed, base-fee and multiplier (e.g. 0.01)
+    #[arg(long, value_parser = parse_gwei, default_value = "1")]
+    priority_fee: U256,
+
+    /// Reward percentile of recent blocks to tip at with --gas-strategy fee-history
+    #[arg(long, default_value_t = 50.0, value_parser = parse_percentile)]
+    fee_percentile: f64,
+
+    /// Recent blocks to take the reward percentile over with --gas-strategy fee-history
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..=1024))]
+    fee_blocks: u64,
+
+    /// Spread the transactions over these priority fees in gwei, in random order, on top of the base fee (e.g. 0,0.01,0.1,1,10)
+    #[arg(long, value_parser = parse_gwei, value_delimiter = ',', conflicts_with = "gas_strategy")]
+    tip_sweep: Vec<U256>,
+
+    /// RPC endpoint URL; repeat or comma-separate several to compare them, sending transactions round-robin
+    #[arg(long, env = "RPC_PROVIDER", value_delimiter = ',')]
+    rpc: Vec<String>,
+
+    /// File listing RPC endpoints to compare, one per line as `URL` or `LABEL URL` (`#` starts a comment)
+    #[arg(long, conflicts_with = "rpc")]
+    rpc_file: Option<PathBuf>,
+
+    /// Send from N sender wallets derived from the main key (or --mnemonic), funded by the main wallet and swept back at the end
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    wallets: Option<u64>,
+
+    /// BIP-39 mnemonic to derive the --wallets senders from, instead of the main key
+    #[arg(long, env = "MNEMONIC", hide_env_values = true)]
+    mnemonic: Option<String>,
 
     /// Private key for the wallet
     #[arg(long, env = "PRIVATE_KEY")]
     pkey: Option<String>,
 }
 
-// Import our custom middlewares
-mod middleware;
-use middleware::sync_transaction::SyncTransactionMiddleware;
-use middleware::realtime_transaction::RealtimeTransactionMiddleware;
-
-/// Sends a transaction and waits for the receipt
-/// This version removes unnecessary await calls to minimize RPC requests
-async fn send_and_confirm_transaction(
-    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    nonce: u64,
-    gas_price: U256,
-) -> Result<(H256, Duration, Duration)> {
-    let address = client.address();
-    
-    // Populate transaction with explicit nonce and hardcoded gas values
-    let mut tx = TypedTransaction::default();
-    tx.set_to(address);
-    tx.set_value(U256::zero());
-    tx.set_nonce(nonce);
-    
-    // Set fixed gas limit - 21000 is the cost of a simple ETH transfer
-    tx.set_gas(21000);
-    
-    // Use the gas price passed from the main function
-    tx.set_gas_price(gas_price);
-    
-    // Start measuring send time
-    let send_start = Instant::now();
-    
-    // Send transaction
-    let pending_tx = client.send_transaction(tx, None).await?;
-    let tx_hash = pending_tx.tx_hash();
-    
-    // Measure send time
-    let send_duration = send_start.elapsed();
-    println!("TX sent in {:?}, hash: {}", send_duration, tx_hash);
-    
-    // Start measuring confirmation time
-    let confirm_start = Instant::now();
-    
-    // Wait for receipt
-    println!("Waiting for confirmation...");
-    let mut receipt: Option<TransactionReceipt> = None;
-    
-    while receipt.is_none() {
-        match client.get_transaction_receipt(tx_hash).await? {
-            Some(r) => {
-                receipt = Some(r.clone());
-                
-                // Print the transaction status in a more readable format
-                let status_str = if let Some(status) = r.status {
-                    if status.low_u32() == 1 { "SUCCESS" } else { "FAILED" }
-                } else {
-                    "UNKNOWN"
-                };
-                
-                println!("\n====== TRANSACTION RECEIPT ======");
-                println!("Transaction Hash: {:?}", r.transaction_hash);
-                println!("Transaction Status: {}", status_str);
-                println!("Block Number: {:?}", r.block_number);
-                println!("Gas Used: {:?}", r.gas_used);
-                println!("================================");
-                break;
-            }
-            None => {
-                // Short sleep to avoid hammering the RPC - slow chain problem, don't use for rise and mega
-                sleep(Duration::from_millis(100)).await;
-            }
+/// Transaction method as chosen on the command line
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum MethodArg {
+    /// Probe the endpoint and use the best method it supports
+    Auto,
+    /// Probe the endpoint and compare every method it supports, interleaving their transactions
+    All,
+    /// `eth_sendRawTransaction` followed by polling `eth_getTransactionReceipt`
+    Async,
+    /// `eth_sendRawTransactionSync`, which returns the receipt directly
+    Rise,
+    /// `realtime_sendRawTransaction`, which returns the receipt directly
+    Mega,
+}
+
+impl MethodArg {
+    /// The explicitly chosen method, or `None` for auto and all
+    fn method(self) -> Option<TxMethod> {
+        match self {
+            MethodArg::Auto | MethodArg::All => None,
+            MethodArg::Async => Some(TxMethod::Async),
+            MethodArg::Rise => Some(TxMethod::Rise),
+            MethodArg::Mega => Some(TxMethod::Mega),
         }
     }
-    
-    // Measure confirmation time
-    let confirm_duration = confirm_start.elapsed();
-    println!("TX confirmed in {:?}", confirm_duration);
-    
-    // Get block information
-    if let Some(r) = receipt {
-        if let Some(block_number) = r.block_number {
-            println!("Included in block: {}", block_number);
+}
+
+/// Workload as chosen on the command line
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum WorkloadArg {
+    /// Zero-value transfer to the sending wallet itself
+    #[value(name = "self")]
+    SelfTransfer,
+    /// ETH transfer of --value to --to
+    Transfer,
+    /// ERC-20 transfer of --amount of --token to --to
+    Erc20,
+    /// Call to --to with calldata from --calldata-file
+    Call,
+    /// Contract deployment from --init-code-file
+    Deploy,
+    /// Call to a gas burner that burns --burn-gas
+    Burn,
+}
+
+impl WorkloadArg {
+    /// Builds the workload from its arguments
+    fn workload(self, args: &Args) -> Result<Workload> {
+        let to = || {
+            let name = self.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+            args.to.ok_or_else(|| anyhow!("--workload {} requires --to", name))
+        };
+        Ok(match self {
+            WorkloadArg::SelfTransfer => Workload::SelfTransfer,
+            WorkloadArg::Transfer => Workload::Transfer { to: to()?, value: args.value },
+            WorkloadArg::Erc20 => Workload::Erc20 {
+                token: args.token.ok_or_else(|| anyhow!("--workload erc20 requires --token"))?,
+                to: to()?,
+                amount: args.amount,
+            },
+            WorkloadArg::Call => Workload::Call {
+                to: to()?,
+                data: match &args.calldata_file {
+                    Some(path) => Workload::read_hex_file(path)?,
+                    None => Default::default(),
+                },
+                value: args.value,
+            },
+            WorkloadArg::Deploy => Workload::Deploy {
+                init_code: match &args.init_code_file {
+                    Some(path) => Workload::read_hex_file(path)?,
+                    None => Workload::burner_init_code(),
+                },
+            },
+            WorkloadArg::Burn => Workload::Burn { contract: args.burn_contract, gas: args.burn_gas },
+        })
+    }
+}
+
+/// Longest sleep between receipt polls with `--poll backoff`
+const BACKOFF_MAX_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Receipt polling strategy as chosen on the command line
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum PollMode {
+    /// Sleep --poll-interval between polls
+    Fixed,
+    /// Start at --poll-interval and double after every miss, up to 1s
+    Backoff,
+    /// Poll again as soon as the previous call returns
+    Hot,
+}
+
+impl PollMode {
+    fn strategy(self, interval: Duration) -> PollStrategy {
+        match self {
+            PollMode::Fixed => PollStrategy::Fixed(interval),
+            PollMode::Backoff => PollStrategy::Backoff { initial: interval, max: BACKOFF_MAX_INTERVAL.max(interval) },
+            PollMode::Hot => PollStrategy::Hot,
         }
     }
-    
-    Ok((tx_hash, send_duration, confirm_duration))
 }
 
+/// Gas pricing strategy as chosen on the command line
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum GasMode {
+    /// --gas-price and --priority-fee for every transaction
+    Fixed,
+    /// --priority-fee on top of the latest base fee
+    BaseFee,
+    /// --gas-multiplier times eth_gasPrice, with --priority-fee
+    Multiplier,
+    /// Tip at --fee-percentile of the rewards in the last --fee-blocks blocks, on top of the base fee
+    FeeHistory,
+    /// Tip suggested by eth_maxPriorityFeePerGas, on top of the base fee
+    Node,
+}
+
+impl GasMode {
+    fn strategy(self, args: &Args) -> GasStrategy {
+        match self {
+            GasMode::Fixed => GasStrategy::Fixed {
+                gas_price: args.gas_price.unwrap_or_default(),
+                priority_fee: args.priority_fee,
+            },
+            GasMode::BaseFee => GasStrategy::BaseFeePlus { priority_fee: args.priority_fee },
+            GasMode::Multiplier => GasStrategy::Multiplier { multiplier: args.gas_multiplier, priority_fee: args.priority_fee },
+            GasMode::FeeHistory => GasStrategy::FeeHistory { blocks: args.fee_blocks, percentile: args.fee_percentile },
+            GasMode::Node => GasStrategy::NodeSuggested,
+        }
+    }
+}
+
+/// Reads endpoints from an `--rpc-file`: one per line, either a bare URL or a label followed by the URL
+fn read_rpc_file(path: &PathBuf) -> Result<Vec<(Option<String>, String)>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let mut endpoints = Vec::new();
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        match line.split_once(char::is_whitespace) {
+            Some((label, url)) => endpoints.push((Some(label.to_string()), url.trim().to_string())),
+            None => endpoints.push((None, line.to_string())),
+        }
+    }
+    Ok(endpoints)
+}
+
+/// Parses a decimal amount in wei or token base units
+fn parse_wei(s: &str) -> Result<U256, String> {
+    U256::from_dec_str(s.trim()).map_err(|e| format!("invalid amount '{}': {}", s, e))
+}
+
+/// Parses a fee in gwei, such as `1`, `0.01` or `0`, into wei
+fn parse_gwei(s: &str) -> Result<U256, String> {
+    ethers::utils::parse_units(s.trim(), "gwei")
+        .map(U256::from)
+        .map_err(|e| format!("invalid gwei amount '{}': {}", s, e))
+}
+
+/// Parses a percentile between 0 and 100
+fn parse_percentile(s: &str) -> Result<f64, String> {
+    let percentile = s.trim().parse::<f64>().map_err(|e| format!("invalid percentile '{}': {}", s, e))?;
+    if (0.0..=100.0).contains(&percentile) {
+        Ok(percentile)
+    } else {
+        Err(format!("percentile must be between 0 and 100, got '{}'", s))
+    }
+}
+
+/// Parses a send rate such as `50`, `50/s` or `600/m` into transactions per second
+fn parse_rate(s: &str) -> Result<f64, String> {
+    let (value, per_secs) = match s.split_once('/') {
+        Some((value, "s")) => (value, 1.0),
+        Some((value, "m")) => (value, 60.0),
+        Some((_, unit)) => return Err(format!("unknown rate unit '{}', expected 's' or 'm'", unit)),
+        None => (s, 1.0),
+    };
+    let rate = value.trim().parse::<f64>().map_err(|e| format!("invalid rate '{}': {}", s, e))? / per_secs;
+    if rate.is_finite() && rate > 0.0 {
+        Ok(rate)
+    } else {
+        Err(format!("rate must be positive, got '{}'", s))
+    }
+}
+
+/// Parses a duration such as `60s`, `500ms` or `2m`; a bare number is taken as seconds
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (value, unit_secs) = if let Some(v) = s.strip_suffix("ms") {
+        (v, 0.001)
+    } else if let Some(v) = s.strip_suffix('s') {
+        (v, 1.0)
+    } else if let Some(v) = s.strip_suffix('m') {
+        (v, 60.0)
+    } else if let Some(v) = s.strip_suffix('h') {
+        (v, 3600.0)
+    } else {
+        (s, 1.0)
+    };
+    let value = value.trim().parse::<f64>().map_err(|e| format!("invalid duration '{}': {}", s, e))?;
+    Duration::try_from_secs_f64(value * unit_secs).map_err(|e| format!("invalid duration '{}': {}", s, e))
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -146,240 +427,224 @@ async fn main() -> Result<()> {
     // Parse command line arguments
     let args = Args::parse();
     
-    // Get RPC URL from command line or environment
-    let rpc_url = args.rpc.expect("RPC_PROVIDER must be set either via --rpc or environment variable");
-    
-    // Auto-detect if we should use eth_sendRawTransactionSync based on RPC URL
-    let method_from_args = args.type_;
-    let method = if rpc_url.to_lowercase().contains("rise") {
-        println!("RPC URL contains 'rise', automatically using eth_sendRawTransactionSync method");
-        TxMethod::Rise
-    } else {
-        method_from_args
+    // Per-transaction progress is logged by the library; print it as plain lines on stdout
+    let log_config = ConfigBuilder::new()
+        .set_max_level(LevelFilter::Off)
+        .set_time_level(LevelFilter::Off)
+        .set_thread_level(LevelFilter::Off)
+        .set_target_level(LevelFilter::Off)
+        .set_location_level(LevelFilter::Off)
+        .add_filter_allow_str("tx_latency")
+        .build();
+    SimpleLogger::init(LevelFilter::Info, log_config)?;
+    
+    // Get RPC URLs from command line, endpoint file or environment
+    let endpoints = match &args.rpc_file {
+        Some(path) => read_rpc_file(path)?,
+        None => args.rpc.iter().map(|url| (None, url.clone())).collect(),
     };
+    if endpoints.is_empty() {
+        return Err(anyhow!("RPC_PROVIDER must be set either via --rpc, --rpc-file or environment variable"));
+    }
     
-    // Get method as string for display
-    let method_str = method.to_string();
-    
+    let workload = args.workload.workload(&args)?;
+    let gas_strategy = args.gas_strategy.map(|mode| mode.strategy(&args));
+
     // Get private key from command line or environment  
     let private_key = args.pkey.expect("PRIVATE_KEY must be set either via --pkey or environment variable");
     
-    let rpc_url_display = rpc_url.clone();
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let wallet: LocalWallet = private_key.parse()?;
-    let wallet_address = wallet.address();
-    let chain_id = provider.get_chainid().await?;
-    let wallet = wallet.with_chain_id(chain_id.as_u64());
-    
-    // Create standard ethers middleware
-    let client = Arc::new(SignerMiddleware::new(provider, wallet));
-    
-    // Create our custom middlewares
-    let sync_client = SyncTransactionMiddleware::new(client.clone());
-    let realtime_client = RealtimeTransactionMiddleware::new(client.clone());
-    
-    // Make necessary RPC calls before the transaction loop
-    let starting_nonce = client.get_transaction_count(wallet_address, None).await?.as_u64();
-    let default_gas_price = client.get_gas_price().await?;
-    // Use 3x the default gas price, or 1 gwei if the gas price is zero
-    let gas_price: U256 = if default_gas_price.is_zero() {
-        println!("Warning: RPC returned zero gas price, using 1 gwei as default");
-        U256::from(1_000_000_000) // 1 gwei
-    } else {
-        default_gas_price * 3
+    let mut builder = Benchmark::builder()
+        .private_key(private_key)
+        .workload(workload)
+        .tx_type(args.tx_type)
+        .blob_proofs(args.blob_proofs)
+        .num_transactions(args.num)
+        .warmup(args.warmup)
+        .concurrency(args.concurrency as usize)
+        .poll_strategy(args.poll.strategy(args.poll_interval))
+        .http_timing(args.http_timing)
+        .observe_interval(args.observe_interval)
+        .observe_timeout(args.observe_timeout)
+        .block_timing(args.block_timing)
+        .head_poll_interval(args.head_interval)
+        .confirmations(args.confirmations)
+        .confirmation_timeout(args.confirmation_timeout);
+    if let Some(finality) = args.finality {
+        builder = builder.finality(finality);
+    }
+    if let Some(timeout) = args.stuck_timeout {
+        let mut stuck_policy = StuckPolicy::new(timeout, args.stuck_action);
+        stuck_policy.fee_bump = args.fee_bump;
+        stuck_policy.max_replacements = args.max_replacements;
+        builder = builder.stuck_policy(stuck_policy);
+    }
+    if let Some(gas_strategy) = gas_strategy {
+        builder = builder.gas_strategy(gas_strategy);
+    }
+    if !args.tip_sweep.is_empty() {
+        builder = builder.tip_sweep(args.tip_sweep.iter().copied());
+    }
+    for observer in args.observer {
+        builder = builder.observer(observer);
+    }
+    for (label, url) in endpoints {
+        builder = match label {
+            Some(label) => builder.endpoint(label, url),
+            None => builder.rpc_url(url),
+        };
+    }
+    builder = match args.type_.method() {
+        Some(method) => builder.method(method),
+        None if args.type_ == MethodArg::All => builder.all_methods(),
+        None => builder.auto_method(),
     };
+    if let Some(wallets) = args.wallets {
+        builder = builder.wallets(wallets as usize);
+    }
+    if let Some(mnemonic) = args.mnemonic {
+        builder = builder.mnemonic(mnemonic);
+    }
+    if let Some(gas_limit) = args.gas_limit {
+        builder = builder.gas_limit(gas_limit);
+    }
+    if let Some(rate) = args.rate {
+        builder = builder.rate(rate);
+    }
+    if let Some(duration) = args.duration {
+        builder = builder.duration(duration);
+    }
+    if let Some(ws) = args.ws {
+        builder = builder.ws_url(ws);
+    }
+    let benchmark = builder.build().await?;
     
     // Display info
-    println!("RPC URL: {}", rpc_url_display);
-    println!("Chain ID: {}", chain_id);
-    println!("Wallet address: {}", wallet_address);
-    println!("Starting nonce: {}", starting_nonce);
-    println!("Default gas price: {} gwei", default_gas_price.as_u64() / 1_000_000_000);
-    println!("Using gas price (3x): {} gwei", gas_price.as_u64() / 1_000_000_000);
-    println!("Transaction method: {}", method_str);
-    
-    // Start timer for entire batch
-    let batch_start_time = Instant::now();
-    
-    // Get number of transactions directly from clap args
-    let num_transactions = args.num;
-    
-    println!("\nSending {} transactions sequentially, waiting for confirmation after each...", num_transactions);
-    
-    let mut results = Vec::with_capacity(num_transactions as usize);
-    
-    for i in 0..num_transactions {
-        let nonce = starting_nonce + i;
-        
-        println!("\n--- Transaction #{} (nonce: {}) ---", i + 1, nonce);
-        
-        // Start timing total transaction time
-        let tx_start = Instant::now();
-        
-        match method {
-            TxMethod::Async => {
-                // Use regular async transaction method
-                match send_and_confirm_transaction(client.clone(), nonce, gas_price).await {
-                    Ok((hash, send_time, confirm_time)) => {
-                        let total_time = tx_start.elapsed();
-                        println!("TX #{}: total time: {:?} (send: {:?}, confirm: {:?})", 
-                                 i + 1, total_time, send_time, confirm_time);
-                        
-                        results.push((hash, send_time, confirm_time, total_time));
-                    },
-                    Err(e) => {
-                        println!("TX #{}: error: {}", i + 1, e);
-                    }
-                }
-            },
-            TxMethod::Rise | TxMethod::Mega => {
-                // Create transaction with explicit nonce and hardcoded gas values
-                // Use EIP-1559 transaction type for compatibility with the sync methods
-                
-                // Ensure we have a non-zero gas price
-                // Gas price is already set to at least 1 gwei in the main function
-                
-                // Set priority fee to 1 gwei
-                let max_priority_fee_per_gas = U256::from(1_000_000_000); // 1 gwei
-                
-                // Make sure max_fee_per_gas is at least as large as max_priority_fee_per_gas
-                let max_fee_per_gas = if gas_price > max_priority_fee_per_gas {
-                    gas_price
-                } else {
-                    // If gas_price is too low, make max_fee at least 2x the priority fee
-                    max_priority_fee_per_gas * 2
-                };
-                
-                // Create EIP-1559 transaction request
-                let tx_request = ethers::types::transaction::eip1559::Eip1559TransactionRequest::new()
-                    .from(wallet_address)
-                    .to(wallet_address)
-                    .value(U256::zero())
-                    .chain_id(chain_id.as_u64())
-                    .nonce(nonce)
-                    .gas(21000)
-                    .max_fee_per_gas(max_fee_per_gas)
-                    .max_priority_fee_per_gas(max_priority_fee_per_gas);
-                    
-                // Convert to TypedTransaction
-                let tx = TypedTransaction::Eip1559(tx_request);
-                
-                // Start measuring send time
-                let send_start = Instant::now();
-                
-                // Sign the transaction
-                let signature = client.signer().sign_transaction(&tx).await?;
-                
-                // Get the properly encoded transaction according to EIP-2718
-                let raw_tx = tx.rlp_signed(&signature);
-                
-                let send_time;
-                let confirm_time = Duration::default();  // Not applicable for sync methods
-                let hash: H256;
-                let receipt: TransactionReceipt;
-                
-                match method {
-                    TxMethod::Rise => {
-                        // Use eth_sendRawTransactionSync
-                        println!("Sending TX #{} with eth_sendRawTransactionSync...", i + 1);
-                        receipt = sync_client.send_raw_transaction_sync(raw_tx).await?;
-                        send_time = send_start.elapsed();
-                        hash = receipt.transaction_hash;
-                    },
-                    TxMethod::Mega => {
-                        // Use realtime_sendRawTransaction
-                        println!("Sending TX #{} with realtime_sendRawTransaction...", i + 1);
-                        receipt = realtime_client.send_raw_transaction_realtime(raw_tx).await?;
-                        send_time = send_start.elapsed();
-                        hash = receipt.transaction_hash;
-                    },
-                    _ => unreachable!(), // This should never happen due to the outer match
-                }
-                
-                let total_time = tx_start.elapsed();
-                
-                // Print the transaction status
-                let status_str = if let Some(status) = receipt.status {
-                    if status.low_u32() == 1 { "SUCCESS" } else { "FAILED" }
-                } else {
-                    "UNKNOWN"
-                };
-                
-                println!("\n====== TRANSACTION RECEIPT ======");
-                println!("Transaction Hash: {}", hash);
-                println!("Transaction Status: {}", status_str);
-                println!("Block Number: {:?}", receipt.block_number);
-                println!("Gas Used: {:?}", receipt.gas_used);
-                println!("================================");
-                
-                // Print block information
-                if let Some(block_number) = receipt.block_number {
-                    println!("Included in block: {}", block_number);
-                }
-                
-                println!("TX #{}: total time: {:?} (send: {:?})", 
-                       i + 1, total_time, send_time);
-                
-                // For sync methods, send time is the total time (confirm time is 0)
-                results.push((hash, send_time, confirm_time, total_time));
+    match benchmark.endpoints() {
+        [endpoint] => println!("RPC URL: {}", endpoint.rpc_url()),
+        endpoints => {
+            println!("RPC endpoints (round-robin):");
+            for endpoint in endpoints {
+                println!("  {}: {}", endpoint.label(), endpoint.rpc_url());
             }
         }
-        
-        println!("--- End Transaction #{} ---\n", i + 1);
+    }
+    println!("Chain ID: {}", benchmark.chain_id());
+    println!("Wallet address: {}", benchmark.wallet_address());
+    if args.wallets.is_some() {
+        println!("Sender wallets (round-robin):");
+        for sender in benchmark.sender_addresses() {
+            println!("  {:?}", sender);
+        }
+    } else {
+        println!("Starting nonce: {}", benchmark.starting_nonce());
+    }
+    println!("Default gas price: {}", fmt_gwei(benchmark.default_gas_price()));
+    match args.gas_strategy {
+        Some(_) => println!("Gas strategy: {} (per transaction)", benchmark.gas_strategy()),
+        None if !benchmark.tip_levels().is_empty() => {
+            let levels: Vec<String> = benchmark.tip_levels().iter().map(|&tip| fmt_gwei(tip)).collect();
+            println!("Tip sweep: {} on top of the latest base fee (random order, about {} transactions each)",
+                levels.join(", "), benchmark.num_transactions() / levels.len() as u64);
+        }
+        None => println!("Using gas price (3x): {}", fmt_gwei(benchmark.gas_price())),
+    }
+    match benchmark.workload() {
+        Workload::Burn { contract: Some(contract), gas } => println!("Workload: burn {} gas in {:?}", gas, contract),
+        workload => println!("Workload: {}", workload.name()),
+    }
+    println!("Gas limit: {}", benchmark.gas_limit());
+    match benchmark.blob_proofs() {
+        Some(blob_proofs) => println!("Transaction type: {} ({} proofs)", benchmark.tx_type(), blob_proofs),
+        None => println!("Transaction type: {}", benchmark.tx_type()),
+    }
+    if args.type_.method().is_none() {
+        for endpoint in benchmark.endpoints() {
+            println!("Probed submission methods ({}):", endpoint.label());
+            for probe in endpoint.probe_results() {
+                let support = if probe.supported { "supported" } else { "not supported" };
+                println!("  {:<5} ({}): {} ({})", probe.method.to_string(), probe.rpc_method, support, probe.detail);
+            }
+        }
+    }
+    if args.type_ == MethodArg::All {
+        let methods: Vec<String> = benchmark.methods().iter().map(ToString::to_string).collect();
+        println!("Transaction methods: {} (compared, interleaved)", methods.join(", "));
+    } else if args.type_ == MethodArg::Auto {
+        println!("Transaction method: {} (auto-selected)", benchmark.method());
+    } else {
+        println!("Transaction method: {}", benchmark.method());
+    }
+    if benchmark.methods().contains(&TxMethod::Async) {
+        match benchmark.ws_url() {
+            Some(ws_url) => println!("Confirmation: newHeads subscription on {}", ws_url),
+            None => println!("Confirmation: receipt polling ({})", args.poll.strategy(args.poll_interval)),
+        }
+        if let Some(stuck_policy) = benchmark.stuck_policy() {
+            println!("Stuck transactions: {}", stuck_policy);
+        }
+    }
+    for observer in benchmark.observers() {
+        println!("Observer: {} ({})", observer.label(), observer.url());
+    }
+    match args.rate {
+        Some(rate) => println!("Mode: open-loop at {:.2} tx/s", rate),
+        None => println!("Concurrency: {}", args.concurrency),
     }
     
-    let batch_elapsed = batch_start_time.elapsed();
-    
-    // Print summary
-    println!("\n===== SUMMARY =====");
-    println!("Total time for all transactions: {:?}", batch_elapsed);
-    println!();
-    
-    println!("Individual Transaction Results:");
-    println!("{:<5} {:<12} {:<12} {:<12} {:<64}", 
-             "TX#", "SEND (ms)", "CONFIRM (ms)", "TOTAL (ms)", "HASH");
-    println!("{}", "-".repeat(120));
+    let report = benchmark.run().await;
+    report.print_summary();
     
-    for (i, (hash, send_time, confirm_time, total_time)) in results.iter().enumerate() {
-        println!("{:<5} {:<12} {:<12} {:<12} {:<64}", 
-                 i + 1,
-                 send_time.as_millis(),
-                 confirm_time.as_millis(),
-                 total_time.as_millis(),
-                 hash);
+    // Sweep before writing the results, so a failed export can't leave the senders funded
+    if args.wallets.is_some() {
+        let (swept, tokens) = benchmark.sweep().await;
+        println!("\nSwept {} wei back to {}", swept, benchmark.wallet_address());
+        if let Workload::Erc20 { token, .. } = benchmark.workload() {
+            println!("Swept {} tokens of {:?} back to {}", tokens, token, benchmark.wallet_address());
+        }
     }
-    
-    // Calculate min, max, and averages
-    if !results.is_empty() {
-        // Send time stats
-        let send_times = results.iter().map(|(_, s, _, _)| s.as_millis() as u128).collect::<Vec<_>>();
-        let min_send = send_times.iter().min().unwrap_or(&0);
-        let max_send = send_times.iter().max().unwrap_or(&0);
-        let avg_send = send_times.iter().sum::<u128>() / send_times.len() as u128;
-
-        // Confirm time stats
-        let confirm_times = results.iter().map(|(_, _, c, _)| c.as_millis() as u128).collect::<Vec<_>>();
-        let min_confirm = confirm_times.iter().min().unwrap_or(&0);
-        let max_confirm = confirm_times.iter().max().unwrap_or(&0);
-        let avg_confirm = confirm_times.iter().sum::<u128>() / confirm_times.len() as u128;
-
-        // Total time stats
-        let total_times = results.iter().map(|(_, _, _, t)| t.as_millis() as u128).collect::<Vec<_>>();
-        let min_total = total_times.iter().min().unwrap_or(&0);
-        let max_total = total_times.iter().max().unwrap_or(&0);
-        let avg_total = total_times.iter().sum::<u128>() / total_times.len() as u128;
-        
-        println!("\nLATENCY STATISTICS:");
-        println!("{:<13} {:<10} {:<10} {:<10}", "", "MIN (ms)", "MAX (ms)", "AVG (ms)");
-        println!("{}", "-".repeat(45));
-        println!("{:<13} {:<10} {:<10} {:<10}", "Send time:", min_send, max_send, avg_send);
-        println!("{:<13} {:<10} {:<10} {:<10}", "Confirm time:", min_confirm, max_confirm, avg_confirm);
-        println!("{:<13} {:<10} {:<10} {:<10}", "Total time:", min_total, max_total, avg_total);
-        
-        println!("\nSUMMARY: {} transactions sent and confirmed sequentially in {} ms (min: {} ms, max: {} ms, avg: {} ms)",
-            results.len(), batch_elapsed.as_millis(), min_total, max_total, avg_total);
-        
+
+    if let Some(format) = args.output {
+        let path = args.out_file.unwrap_or_else(|| format.default_path());
+        report.write(format, &path)?;
+        println!("\nResults written to {}", path.display());
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rate_accepts_per_second_and_per_minute() {
+        assert_eq!(parse_rate("50"), Ok(50.0));
+        assert_eq!(parse_rate("50/s"), Ok(50.0));
+        assert_eq!(parse_rate("600/m"), Ok(10.0));
+        assert_eq!(parse_rate("0.5"), Ok(0.5));
+    }
+
+    #[test]
+    fn parse_rate_rejects_bad_units_and_non_positive_rates() {
+        assert!(parse_rate("5/h").is_err());
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("-1/s").is_err());
+        assert!(parse_rate("fast").is_err());
+    }
+
+    #[test]
+    fn parse_duration_accepts_units() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("60s"), Ok(Duration::from_secs(60)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1.5h"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration(" 30 "), Ok(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn parse_duration_rejects_malformed_and_negative_values() {
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("10d").is_err());
+        assert!(parse_duration("-1s").is_err());
+    }
+}
@@ -1,2 +1,3 @@
+pub mod sync_submit;
 pub mod sync_transaction;
 pub mod realtime_transaction;
\ No newline at end of file
@@ -1,42 +1,20 @@
-use anyhow::Result;
 use ethers::{
     core::types::Bytes,
-    middleware::{Middleware, MiddlewareError},
-    providers::JsonRpcClient,
+    middleware::Middleware,
     types::TransactionReceipt,
 };
-use thiserror::Error;
 
-#[derive(Debug, Error)]
-pub enum RealtimeMiddlewareError<M: Middleware> {
-    #[error("Middleware error: {0}")]
-    MiddlewareError(M::Error),
+use super::sync_submit::{SyncSubmit, SyncSubmitError, SyncSubmitMiddleware};
 
-    #[error("RPC error: {0}")]
-    RpcError(String),
-}
-
-impl<M: Middleware> MiddlewareError for RealtimeMiddlewareError<M> {
-    type Inner = M::Error;
-
-    fn from_err(src: M::Error) -> Self {
-        Self::MiddlewareError(src)
-    }
+/// RPC method that both sends a raw transaction and waits for its receipt in a single call
+pub const METHOD: &str = "realtime_sendRawTransaction";
 
-    fn as_inner(&self) -> Option<&Self::Inner> {
-        match self {
-            Self::MiddlewareError(e) => Some(e),
-            _ => None,
-        }
-    }
-}
+pub type RealtimeMiddlewareError<M> = SyncSubmitError<M>;
 
 /// RealtimeTransactionMiddleware provides access to the `realtime_sendRawTransaction` RPC method
 /// which both sends and waits for transaction receipt in a single call
 #[derive(Debug)]
-pub struct RealtimeTransactionMiddleware<M> {
-    inner: M,
-}
+pub struct RealtimeTransactionMiddleware<M>(SyncSubmitMiddleware<M>);
 
 impl<M> RealtimeTransactionMiddleware<M>
 where
@@ -44,7 +22,7 @@ where
 {
     /// Create a new instance of the RealtimeTransactionMiddleware
     pub fn new(inner: M) -> Self {
-        Self { inner }
+        Self(SyncSubmitMiddleware::new(inner, METHOD))
     }
 
     /// Send a raw transaction using the `realtime_sendRawTransaction` RPC method
@@ -54,19 +32,24 @@ where
         raw_tx: Bytes,
     ) -> Result<TransactionReceipt, RealtimeMiddlewareError<M>>
     where
-        M: Middleware,
-        M::Provider: JsonRpcClient,
+        M: 'static,
     {
-        let provider = self.inner.provider();
-        
-        // Ensure the byte sequence is properly prefixed according to EIP-2718 format
-        let hex_value = format!("0x{}", hex::encode(&raw_tx));
-        let params = [serde_json::Value::String(hex_value)];
-        
-        provider
-            .request("realtime_sendRawTransaction", params)
-            .await
-            .map_err(|e| RealtimeMiddlewareError::RpcError(e.to_string()))
+        self.0.send_raw_and_wait(raw_tx).await
+    }
+}
+
+impl<M> SyncSubmit for RealtimeTransactionMiddleware<M>
+where
+    M: Middleware + 'static,
+{
+    type Error = RealtimeMiddlewareError<M>;
+
+    fn method_name(&self) -> &str {
+        METHOD
+    }
+
+    async fn send_raw_and_wait(&self, raw_tx: Bytes) -> Result<TransactionReceipt, Self::Error> {
+        self.0.send_raw_and_wait(raw_tx).await
     }
 }
 
@@ -80,6 +63,6 @@ where
     type Inner = M;
 
     fn inner(&self) -> &M {
-        &self.inner
+        self.0.inner()
     }
 }
@@ -0,0 +1,114 @@
+use ethers::{
+    core::types::Bytes,
+    middleware::{Middleware, MiddlewareError},
+    providers::ProviderError,
+    types::TransactionReceipt,
+};
+use std::future::Future;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SyncSubmitError<M: Middleware> {
+    #[error("Middleware error: {0}")]
+    MiddlewareError(M::Error),
+
+    #[error("RPC error: {0}")]
+    RpcError(ProviderError),
+}
+
+impl<M: Middleware> MiddlewareError for SyncSubmitError<M> {
+    type Inner = M::Error;
+
+    fn from_err(src: M::Error) -> Self {
+        Self::MiddlewareError(src)
+    }
+
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        match self {
+            Self::MiddlewareError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An RPC method that submits a signed raw transaction and only returns once it has a receipt,
+/// such as `eth_sendRawTransactionSync` or `realtime_sendRawTransaction`
+pub trait SyncSubmit {
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Name of the JSON-RPC method used to submit
+    fn method_name(&self) -> &str;
+
+    /// Submit an EIP-2718 encoded signed transaction and wait for its receipt in a single call
+    fn send_raw_and_wait(
+        &self,
+        raw_tx: Bytes,
+    ) -> impl Future<Output = Result<TransactionReceipt, Self::Error>> + Send;
+}
+
+/// SyncSubmitMiddleware calls any sync-submit RPC method by name, so chains with their own
+/// variant of `eth_sendRawTransactionSync` don't need a dedicated middleware
+#[derive(Debug, Clone)]
+pub struct SyncSubmitMiddleware<M> {
+    inner: M,
+    method: String,
+}
+
+impl<M> SyncSubmitMiddleware<M>
+where
+    M: Middleware,
+{
+    /// Create a new instance that submits with the given RPC method
+    pub fn new(inner: M, method: impl Into<String>) -> Self {
+        Self {
+            inner,
+            method: method.into(),
+        }
+    }
+}
+
+impl<M> SyncSubmit for SyncSubmitMiddleware<M>
+where
+    M: Middleware + 'static,
+{
+    type Error = SyncSubmitError<M>;
+
+    fn method_name(&self) -> &str {
+        &self.method
+    }
+
+    async fn send_raw_and_wait(&self, raw_tx: Bytes) -> Result<TransactionReceipt, Self::Error> {
+        request_receipt(&self.inner, &self.method, raw_tx).await
+    }
+}
+
+// Implement Middleware trait so it can be used in middleware chain
+impl<M> Middleware for SyncSubmitMiddleware<M>
+where
+    M: Middleware,
+{
+    type Error = SyncSubmitError<M>;
+    type Provider = M::Provider;
+    type Inner = M;
+
+    fn inner(&self) -> &M {
+        &self.inner
+    }
+}
+
+/// Sends `raw_tx` with the sync-submit RPC `method` and decodes the receipt it returns
+async fn request_receipt<M: Middleware>(
+    inner: &M,
+    method: &str,
+    raw_tx: Bytes,
+) -> Result<TransactionReceipt, SyncSubmitError<M>> {
+    // Ensure the byte sequence is properly prefixed according to EIP-2718 format
+    let hex_value = format!("0x{}", hex::encode(&raw_tx));
+    let params = [serde_json::Value::String(hex_value)];
+
+    inner
+        .provider()
+        .request(method, params)
+        .await
+        .map_err(SyncSubmitError::RpcError)
+}
@@ -0,0 +1,149 @@
+use anyhow::Result;
+use ethers::{
+    providers::Middleware,
+    types::{BlockNumber, TransactionRequest, H256, U256},
+};
+use log::{info, warn};
+use std::sync::Mutex;
+
+use crate::gas::GasStrategy;
+use crate::Client;
+
+/// Hands out consecutive nonces for a wallet. A single manager is shared by every endpoint, so
+/// transactions sent through different RPCs still form one gap-free nonce sequence.
+#[derive(Debug)]
+pub struct NonceManager {
+    next: Mutex<u64>,
+}
+
+impl NonceManager {
+    pub fn new(starting_nonce: u64) -> Self {
+        Self { next: Mutex::new(starting_nonce) }
+    }
+
+    /// Reserves the next nonce
+    pub fn reserve(&self) -> u64 {
+        let mut next = self.next.lock().unwrap();
+        *next += 1;
+        *next - 1
+    }
+
+    /// Gives back a reserved nonce whose transaction never reached the node. Returns `true` if it
+    /// was the latest reservation and will be handed out again, or `false` if later nonces were
+    /// reserved since, in which case it is a gap that has to be filled.
+    pub fn release(&self, nonce: u64) -> bool {
+        let mut next = self.next.lock().unwrap();
+        if *next == nonce + 1 {
+            *next = nonce;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Skips ahead to `pending`, the node's pending transaction count, after the manager and the
+    /// node were found to disagree. Never moves back: with transactions in flight the node may not
+    /// have seen nonces already handed out, and reusing them would collide. Returns the nonce that
+    /// would have been handed out next.
+    pub fn resync(&self, pending: u64) -> u64 {
+        let mut next = self.next.lock().unwrap();
+        let previous = *next;
+        *next = previous.max(pending);
+        previous
+    }
+}
+
+/// Makes sure a failed transaction with `nonce` doesn't stall the wallet's later transactions.
+///
+/// A "nonce too low" error resyncs the manager from `eth_getTransactionCount(pending)`. Otherwise,
+/// including after "nonce too high", if the node hasn't used the nonce, it is released, or filled
+/// with a no-op self-transfer priced by `gas_strategy` if later nonces are already out. Problems
+/// are logged, since the failure itself is already being reported.
+pub async fn recover(client: &Client, nonces: &NonceManager, nonce: u64, error: &str, gas_strategy: &GasStrategy) {
+    let pending = match client.get_transaction_count(client.address(), Some(BlockNumber::Pending.into())).await {
+        Ok(pending) => pending.as_u64(),
+        Err(e) => {
+            warn!("Failed to fetch the pending nonce of {:?}: {}", client.address(), e);
+            return;
+        }
+    };
+
+    if pending > nonce && error.to_lowercase().contains("nonce too low") {
+        let previous = nonces.resync(pending);
+        if pending > previous {
+            warn!("Nonce out of sync: resynced from {} to the pending count {}", previous, pending);
+        } else {
+            warn!("Nonce out of sync: keeping {}, ahead of the pending count {}, as lower nonces may still be in flight",
+                  previous, pending);
+        }
+    } else if pending > nonce {
+        // The transaction (or another one with its nonce) reached the node after all
+    } else if nonces.release(nonce) {
+        info!("Released nonce {}", nonce);
+    } else {
+        match fill_gap(client, nonce, gas_strategy).await {
+            Ok(tx_hash) => info!("Filled nonce gap {} with a no-op self-transfer: {:?}", nonce, tx_hash),
+            Err(e) => warn!("Failed to fill nonce gap {}: {}", nonce, e),
+        }
+    }
+}
+
+/// Sends a zero-value self-transfer with `nonce` at the gas price `gas_strategy` chooses now,
+/// without waiting for it to be included
+async fn fill_gap(client: &Client, nonce: u64, gas_strategy: &GasStrategy) -> Result<H256> {
+    let gas_price = gas_strategy.fees(client).await?.gas_price;
+    let tx = TransactionRequest::new()
+        .from(client.address())
+        .to(client.address())
+        .value(U256::zero())
+        .nonce(nonce)
+        .gas(21000)
+        .gas_price(gas_price);
+    Ok(client.send_transaction(tx, None).await?.tx_hash())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserve_hands_out_consecutive_nonces() {
+        let nonces = NonceManager::new(5);
+        assert_eq!(nonces.reserve(), 5);
+        assert_eq!(nonces.reserve(), 6);
+        assert_eq!(nonces.reserve(), 7);
+    }
+
+    #[test]
+    fn release_of_latest_nonce_hands_it_out_again() {
+        let nonces = NonceManager::new(5);
+        let nonce = nonces.reserve();
+        assert!(nonces.release(nonce));
+        assert_eq!(nonces.reserve(), nonce);
+    }
+
+    #[test]
+    fn release_of_earlier_nonce_leaves_a_gap() {
+        let nonces = NonceManager::new(5);
+        let first = nonces.reserve();
+        nonces.reserve();
+        assert!(!nonces.release(first));
+        assert_eq!(nonces.reserve(), 7);
+    }
+
+    #[test]
+    fn resync_moves_forward() {
+        let nonces = NonceManager::new(5);
+        assert_eq!(nonces.resync(9), 5);
+        assert_eq!(nonces.reserve(), 9);
+    }
+
+    #[test]
+    fn resync_never_moves_back() {
+        let nonces = NonceManager::new(5);
+        nonces.reserve();
+        nonces.reserve();
+        assert_eq!(nonces.resync(5), 7);
+        assert_eq!(nonces.reserve(), 7);
+    }
+}
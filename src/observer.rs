@@ -0,0 +1,195 @@
+use anyhow::Result;
+use ethers::{
+    providers::{JsonRpcClient, Middleware, Provider, StreamExt, Ws},
+    types::H256,
+};
+use log::warn;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::oneshot,
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
+
+use crate::subscription::{subscribed, wake, HashSubscription, Waiters};
+use crate::transport::Transport;
+
+/// When and whether one observer saw a transaction, measured from the moment it was sent
+#[derive(Debug, Clone)]
+pub struct Observation {
+    /// Label of the observer endpoint
+    pub observer: String,
+    /// Time until the observer knew the transaction, from its mempool or, if it went straight into
+    /// a block, its receipt
+    pub seen: Option<Duration>,
+    /// Time until the observer returned a receipt for it
+    pub receipt: Option<Duration>,
+}
+
+/// How an observer learns about transactions it hasn't been sent
+#[derive(Debug)]
+enum Feed {
+    /// Polls `eth_getTransactionByHash` until the transaction shows up
+    Poll(Provider<Transport>),
+    /// Follows a `newPendingTransactions` subscription
+    Subscription {
+        provider: Provider<Ws>,
+        pending: HashSubscription<Instant>,
+    },
+}
+
+/// An endpoint that is watched for transactions submitted through another one, to measure how
+/// long they take to propagate
+#[derive(Debug)]
+pub struct Observer {
+    label: String,
+    url: String,
+    feed: Feed,
+    poll_interval: Duration,
+    timeout: Duration,
+}
+
+impl Observer {
+    /// Connects to an observer. A `ws://` or `wss://` URL is followed over a pending-transaction
+    /// subscription, anything else is polled every `poll_interval`. Observation of a transaction
+    /// gives up after `timeout`.
+    pub async fn connect(label: String, url: &str, poll_interval: Duration, timeout: Duration) -> Result<Self> {
+        let feed = if url.starts_with("ws://") || url.starts_with("wss://") {
+            let provider = Provider::<Ws>::connect(url).await?;
+            let follower = provider.clone();
+            let pending = HashSubscription::spawn("pending transaction", |waiters, ready| {
+                follow_pending(follower, waiters, ready)
+            })
+            .await?;
+            Feed::Subscription { provider, pending }
+        } else {
+            Feed::Poll(Provider::new(Transport::http(url)?))
+        };
+        Ok(Self { label, url: url.to_string(), feed, poll_interval, timeout })
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Starts watching for `tx_hash`, which is about to be sent; `sent_at` is taken now.
+    /// Call before sending so a subscription can't miss the transaction.
+    pub fn observe(self: &Arc<Self>, tx_hash: H256) -> JoinHandle<Observation> {
+        let announced = match &self.feed {
+            Feed::Subscription { pending, .. } => Some(pending.watch(tx_hash)),
+            Feed::Poll(_) => None,
+        };
+        let sent_at = Instant::now();
+        let observer = self.clone();
+
+        tokio::spawn(async move {
+            let (seen, receipt) = match &observer.feed {
+                Feed::Subscription { provider, pending } => {
+                    let announcement = async {
+                        let at = timeout(observer.timeout, announced?).await.ok()?.ok()?;
+                        Some(at.saturating_duration_since(sent_at))
+                    };
+                    let receipt = observer.wait_for_receipt(provider, tx_hash, sent_at);
+                    tokio::pin!(announcement, receipt);
+                    // A transaction can reach a block without passing through the observer's
+                    // mempool; then the observer first knows it from its receipt
+                    let observed = tokio::select! {
+                        seen = &mut announcement => {
+                            let receipt = receipt.await;
+                            (seen.or(receipt), receipt)
+                        }
+                        receipt = &mut receipt => (receipt, receipt),
+                    };
+                    pending.unwatch(tx_hash);
+                    observed
+                }
+                Feed::Poll(provider) => {
+                    let seen = observer.poll_seen(provider, tx_hash, sent_at).await;
+                    let receipt = match seen {
+                        Some(_) => observer.wait_for_receipt(provider, tx_hash, sent_at).await,
+                        None => None,
+                    };
+                    (seen, receipt)
+                }
+            };
+            Observation { observer: observer.label.clone(), seen, receipt }
+        })
+    }
+
+    /// Polls `eth_getTransactionByHash` until the observer knows the transaction
+    async fn poll_seen<P: JsonRpcClient>(&self, provider: &Provider<P>, tx_hash: H256, sent_at: Instant) -> Option<Duration> {
+        while sent_at.elapsed() < self.timeout {
+            match provider.get_transaction(tx_hash).await {
+                Ok(Some(_)) => return Some(sent_at.elapsed()),
+                Ok(None) => {}
+                Err(e) => warn!("Observer {}: eth_getTransactionByHash failed: {}", self.label, e),
+            }
+            sleep(self.poll_interval).await;
+        }
+        None
+    }
+
+    /// Polls `eth_getTransactionReceipt` until the observer has the receipt
+    async fn wait_for_receipt<P: JsonRpcClient>(&self, provider: &Provider<P>, tx_hash: H256, sent_at: Instant) -> Option<Duration> {
+        while sent_at.elapsed() < self.timeout {
+            match provider.get_transaction_receipt(tx_hash).await {
+                Ok(Some(_)) => return Some(sent_at.elapsed()),
+                Ok(None) => {}
+                Err(e) => warn!("Observer {}: eth_getTransactionReceipt failed: {}", self.label, e),
+            }
+            sleep(self.poll_interval).await;
+        }
+        None
+    }
+}
+
+/// Observations of one transaction that are still running
+#[derive(Debug, Default)]
+pub struct PendingObservations(Vec<JoinHandle<Observation>>);
+
+impl PendingObservations {
+    /// Starts observing `tx_hash` on every observer
+    pub fn start(observers: &[Arc<Observer>], tx_hash: H256) -> Self {
+        Self(observers.iter().map(|observer| observer.observe(tx_hash)).collect())
+    }
+
+    /// Waits until every observer has seen the receipt or timed out
+    pub async fn collect(mut self) -> Vec<Observation> {
+        let mut observations = Vec::with_capacity(self.0.len());
+        for handle in std::mem::take(&mut self.0) {
+            match handle.await {
+                Ok(observation) => observations.push(observation),
+                Err(e) => warn!("Observation task failed: {}", e),
+            }
+        }
+        observations
+    }
+}
+
+impl Drop for PendingObservations {
+    /// Stops watching when the transaction failed to send
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
+    }
+}
+
+/// Subscription loop: records when each watched transaction hash is announced
+async fn follow_pending(provider: Provider<Ws>, waiters: Waiters<Instant>, ready: oneshot::Sender<Result<()>>) {
+    let Some(mut pending) = subscribed(provider.subscribe_pending_txs().await, ready) else {
+        return;
+    };
+
+    while let Some(tx_hash) = pending.next().await {
+        wake(&waiters, &tx_hash, Instant::now());
+    }
+    warn!("newPendingTransactions subscription ended");
+}
@@ -0,0 +1,90 @@
+use ethers::providers::{JsonRpcClient, Provider, ProviderError, RpcError};
+use serde::Serialize;
+
+use crate::submitter::TxMethod;
+
+/// An empty RLP list, which no node can decode as a transaction, so probing never spends anything.
+/// An endpoint that implements the method rejects it with a decoding error; one that doesn't
+/// rejects it as an unknown method. (A leading byte below 0x7f would instead be read as a typed
+/// transaction and could be rejected as "transaction type not supported".)
+const MALFORMED_TX: &str = "0xc0";
+
+/// Order in which `--type auto` prefers the submission methods: sync methods first, since they
+/// return the receipt in the same call
+pub const AUTO_PREFERENCE: [TxMethod; 3] = [TxMethod::Rise, TxMethod::Mega, TxMethod::Async];
+
+/// Order in which `--type all` runs the methods it compares, with async as the baseline
+pub const COMPARISON_ORDER: [TxMethod; 3] = [TxMethod::Async, TxMethod::Rise, TxMethod::Mega];
+
+/// Error messages nodes use when they don't implement a method, for those that don't
+/// answer with the standard -32601 code
+const UNSUPPORTED_MESSAGES: [&str; 4] = ["not found", "does not exist", "not supported", "not available"];
+
+/// Whether an endpoint implements the RPC method behind a submission method
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeResult {
+    pub method: TxMethod,
+    /// The JSON-RPC method that was probed
+    pub rpc_method: &'static str,
+    pub supported: bool,
+    /// What the endpoint answered, for display
+    pub detail: String,
+}
+
+/// Checks whether the endpoint implements `method` by submitting a malformed transaction with it
+pub async fn probe_method<P: JsonRpcClient>(provider: &Provider<P>, method: TxMethod) -> ProbeResult {
+    let rpc_method = method.submit_rpc_method();
+    let response: Result<serde_json::Value, ProviderError> = provider.request(rpc_method, [MALFORMED_TX]).await;
+
+    let (supported, detail) = match response {
+        // Nothing should accept this payload, but whatever did clearly knows the method
+        Ok(_) => (true, "accepted a malformed transaction".to_string()),
+        Err(e) => match e.as_error_response() {
+            Some(rpc_error) => (!is_method_unsupported(rpc_error.code, &rpc_error.message), rpc_error.message.clone()),
+            // No JSON-RPC error at all (e.g. an HTTP error page), so we can't tell it's there
+            None => (false, e.to_string()),
+        },
+    };
+
+    ProbeResult { method, rpc_method, supported, detail }
+}
+
+/// Probes every submission method, in [`AUTO_PREFERENCE`] order
+pub async fn probe_all<P: JsonRpcClient>(provider: &Provider<P>) -> Vec<ProbeResult> {
+    let mut results = Vec::with_capacity(AUTO_PREFERENCE.len());
+    for method in AUTO_PREFERENCE {
+        results.push(probe_method(provider, method).await);
+    }
+    results
+}
+
+/// The most preferred method supported by every endpoint, given one set of probe results per
+/// endpoint, falling back to async, which every endpoint is assumed to support
+pub fn select_common_method(results: &[Vec<ProbeResult>]) -> TxMethod {
+    AUTO_PREFERENCE
+        .into_iter()
+        .find(|method| {
+            results
+                .iter()
+                .all(|endpoint| endpoint.iter().any(|r| r.method == *method && r.supported))
+        })
+        .unwrap_or(TxMethod::Async)
+}
+
+/// Every method supported by all endpoints, in [`COMPARISON_ORDER`]
+pub fn common_methods(results: &[Vec<ProbeResult>]) -> Vec<TxMethod> {
+    COMPARISON_ORDER
+        .into_iter()
+        .filter(|method| {
+            results
+                .iter()
+                .all(|endpoint| endpoint.iter().any(|r| r.method == *method && r.supported))
+        })
+        .collect()
+}
+
+/// Classifies a JSON-RPC error as "method not implemented" rather than "bad transaction"
+fn is_method_unsupported(code: i64, message: &str) -> bool {
+    let message = message.to_lowercase();
+    code == -32601 || UNSUPPORTED_MESSAGES.iter().any(|m| message.contains(m))
+}
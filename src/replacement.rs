@@ -0,0 +1,241 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use ethers::{
+    middleware::Middleware,
+    signers::Signer,
+    types::{transaction::eip2718::TypedTransaction, Bytes, TransactionReceipt, H256, U256},
+};
+use log::{info, warn};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use crate::Client;
+
+/// Gas limit of the zero-value self-transfer that cancels a stuck transaction
+const CANCEL_GAS: u64 = 21_000;
+
+/// What to do with a transaction that still has no receipt after the stuck timeout
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StuckAction {
+    /// Send the same signed transaction again, in case the node dropped or never propagated it
+    Rebroadcast,
+    /// Replace it with the same transaction at higher fees
+    SpeedUp,
+    /// Replace it with a zero-value self-transfer at higher fees, freeing the nonce
+    Cancel,
+}
+
+impl std::fmt::Display for StuckAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StuckAction::Rebroadcast => write!(f, "rebroadcast"),
+            StuckAction::SpeedUp => write!(f, "speed-up"),
+            StuckAction::Cancel => write!(f, "cancel"),
+        }
+    }
+}
+
+/// When and how transactions without a receipt are replaced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StuckPolicy {
+    /// Time without a receipt after the last send before acting again
+    pub timeout: Duration,
+    pub action: StuckAction,
+    /// Fee increase of every speed-up or cancellation, in percent; nodes usually require at least 10
+    pub fee_bump: u64,
+    /// Replacements sent before giving up on the transaction one timeout after the last one
+    pub max_replacements: u32,
+}
+
+impl StuckPolicy {
+    pub fn new(timeout: Duration, action: StuckAction) -> Self {
+        Self { timeout, action, fee_bump: 10, max_replacements: 3 }
+    }
+
+    /// Raises a fee by the bump, rounding up so the replacement clears the node's minimum bump.
+    /// Nodes also require every fee to strictly rise, so a zero fee still goes up by 1 wei.
+    fn bump(&self, fee: U256) -> U256 {
+        ((fee * (100 + self.fee_bump) + 99) / 100).max(fee + 1)
+    }
+
+    /// Highest fee per gas a transaction starting at `fee` may end up paying
+    pub fn max_fee(&self, fee: U256) -> U256 {
+        match self.action {
+            StuckAction::Rebroadcast => fee,
+            StuckAction::SpeedUp | StuckAction::Cancel => (0..self.max_replacements).fold(fee, |fee, _| self.bump(fee)),
+        }
+    }
+}
+
+impl std::fmt::Display for StuckPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} after {:?}", self.action, self.timeout)?;
+        if self.action != StuckAction::Rebroadcast {
+            write!(f, " (+{}% fees)", self.fee_bump)?;
+        }
+        write!(f, ", up to {} times", self.max_replacements)
+    }
+}
+
+/// A transaction sent with the nonce of a stuck one
+#[derive(Debug, Clone, Copy)]
+pub struct Replacement {
+    pub action: StuckAction,
+    /// Transaction it replaced; the same as `hash` for a rebroadcast
+    pub replaces: H256,
+    pub hash: H256,
+    /// When it was sent, measured from sending the original transaction
+    pub after: Duration,
+    /// Highest fee per gas it pays
+    pub fee_per_gas: U256,
+}
+
+/// Every transaction sent with one nonce, any of which may be the one that lands
+#[derive(Debug)]
+pub struct ReplacementChain {
+    /// Latest transaction sent, and its signed encoding
+    tx: TypedTransaction,
+    raw_tx: Bytes,
+    hash: H256,
+    /// Distinct hashes sent so far, the original first
+    hashes: Vec<H256>,
+    replacements: Vec<Replacement>,
+    /// Replacements attempted, including ones the node rejected
+    attempts: u32,
+    cancelled: bool,
+    sent_at: Instant,
+    last_sent_at: Instant,
+}
+
+impl ReplacementChain {
+    /// Starts the chain with the original transaction, sent as `hash` at `sent_at`
+    pub fn new(tx: TypedTransaction, raw_tx: Bytes, hash: H256, sent_at: Instant) -> Self {
+        Self {
+            tx,
+            raw_tx,
+            hash,
+            hashes: vec![hash],
+            replacements: Vec::new(),
+            attempts: 0,
+            cancelled: false,
+            sent_at,
+            last_sent_at: sent_at,
+        }
+    }
+
+    /// Hashes any of which may still land, the original first
+    pub fn hashes(&self) -> &[H256] {
+        &self.hashes
+    }
+
+    /// Replacements sent so far, in order
+    pub fn replacements(&self) -> &[Replacement] {
+        &self.replacements
+    }
+
+    /// Whether `hash` is one of the cancelling self-transfers
+    pub fn is_cancellation(&self, hash: H256) -> bool {
+        self.replacements.iter().any(|r| r.action == StuckAction::Cancel && r.hash == hash)
+    }
+
+    /// Looks for a receipt of any transaction in the chain, making one call per hash
+    pub async fn receipt(&self, client: &Client) -> Result<Option<TransactionReceipt>> {
+        for &hash in &self.hashes {
+            if let Some(receipt) = client.get_transaction_receipt(hash).await? {
+                return Ok(Some(receipt));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Time left until the policy acts again
+    pub fn until_due(&self, policy: &StuckPolicy) -> Duration {
+        policy.timeout.saturating_sub(self.last_sent_at.elapsed())
+    }
+
+    /// Applies the policy once its timeout has passed since the last send. A replacement the node
+    /// rejects is logged and retried after another timeout, since the original may have just
+    /// landed. Errors once the policy has run out of replacements.
+    pub async fn check(&mut self, client: &Client, policy: &StuckPolicy) -> Result<()> {
+        if !self.until_due(policy).is_zero() {
+            return Ok(());
+        }
+        let nonce = self.tx.nonce().copied().unwrap_or_default();
+        if self.attempts >= policy.max_replacements {
+            return Err(anyhow!(
+                "no receipt for nonce {} after {:?} and {} replacements",
+                nonce, self.sent_at.elapsed(), self.replacements.len()
+            ));
+        }
+        self.attempts += 1;
+        self.last_sent_at = Instant::now();
+        match self.replace(client, policy).await {
+            Ok(replacement) => info!("Nonce {} stuck: {} as {:?} at {} wei/gas",
+                                     nonce, policy.action, replacement.hash, replacement.fee_per_gas),
+            Err(e) => warn!("Failed to {} nonce {}: {}", policy.action, nonce, e),
+        }
+        Ok(())
+    }
+
+    /// Sends the policy's replacement of the latest transaction
+    async fn replace(&mut self, client: &Client, policy: &StuckPolicy) -> Result<Replacement> {
+        let mut tx = self.tx.clone();
+        if policy.action == StuckAction::Cancel && !self.cancelled {
+            let from = client.address();
+            tx.set_to(from);
+            tx.set_value(U256::zero());
+            tx.set_data(Bytes::default());
+            tx.set_gas(CANCEL_GAS);
+        }
+        let raw_tx = match policy.action {
+            StuckAction::Rebroadcast => self.raw_tx.clone(),
+            StuckAction::SpeedUp | StuckAction::Cancel => {
+                bump_fees(&mut tx, policy);
+                let signature = client.signer().sign_transaction(&tx).await?;
+                tx.rlp_signed(&signature)
+            }
+        };
+
+        let hash = match client.send_raw_transaction(raw_tx.clone()).await {
+            Ok(pending_tx) => pending_tx.tx_hash(),
+            // The node still has it, which is all a rebroadcast is for
+            Err(e) if policy.action == StuckAction::Rebroadcast && e.to_string().contains("already known") => self.hash,
+            Err(e) => return Err(e.into()),
+        };
+        let replacement = Replacement {
+            action: policy.action,
+            replaces: self.hash,
+            hash,
+            after: self.sent_at.elapsed(),
+            fee_per_gas: tx.gas_price().unwrap_or_default(),
+        };
+        if !self.hashes.contains(&hash) {
+            self.hashes.push(hash);
+        }
+        self.replacements.push(replacement);
+        self.cancelled |= policy.action == StuckAction::Cancel;
+        self.tx = tx;
+        self.raw_tx = raw_tx;
+        self.hash = hash;
+        Ok(replacement)
+    }
+
+    /// Ends the chain, returning the replacements sent
+    pub fn into_replacements(self) -> Vec<Replacement> {
+        self.replacements
+    }
+}
+
+/// Raises every fee of `tx` by the policy's bump: the gas price, or both the max fee and the
+/// priority fee, as nodes require both to rise for an EIP-1559 replacement
+fn bump_fees(tx: &mut TypedTransaction, policy: &StuckPolicy) {
+    match tx {
+        TypedTransaction::Legacy(tx) => tx.gas_price = tx.gas_price.map(|fee| policy.bump(fee)),
+        TypedTransaction::Eip2930(tx) => tx.tx.gas_price = tx.tx.gas_price.map(|fee| policy.bump(fee)),
+        TypedTransaction::Eip1559(tx) => {
+            tx.max_fee_per_gas = tx.max_fee_per_gas.map(|fee| policy.bump(fee));
+            tx.max_priority_fee_per_gas = tx.max_priority_fee_per_gas.map(|fee| policy.bump(fee));
+        }
+    }
+}
@@ -0,0 +1,759 @@
+use anyhow::Result;
+use ethers::types::{Address, TransactionReceipt, H256, U256, U64};
+use serde::Serialize;
+use std::{path::Path, time::Duration};
+
+use crate::export::{self, OutputFormat, RunMetadata};
+use crate::gas::fmt_gwei;
+use crate::stats::{fmt_ms, LatencyStats, PERCENTILES};
+use crate::observer::Observation;
+use crate::replacement::Replacement;
+use crate::submitter::{TxMethod, TxType};
+use crate::transport::HttpTiming;
+
+/// Quantiles of each metric in the endpoint and wallet comparisons
+const QUANTILES: [(&str, f64); 3] = [("p50", 0.5), ("p90", 0.9), ("p99", 0.99)];
+
+/// Final state of a benchmarked transaction
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxStatus {
+    /// Included with receipt status 1
+    Success,
+    /// Included but reverted (receipt status 0)
+    Reverted,
+    /// Included, but the receipt has no status field (pre-Byzantium)
+    Unknown,
+    /// Never got a receipt: signing, submission or receipt polling failed
+    Error,
+    /// Stuck and replaced by a zero-value self-transfer, which landed instead
+    Cancelled,
+}
+
+impl TxStatus {
+    pub fn from_receipt(receipt: &TransactionReceipt) -> Self {
+        match receipt.status {
+            Some(status) if status == U64::one() => TxStatus::Success,
+            Some(_) => TxStatus::Reverted,
+            None => TxStatus::Unknown,
+        }
+    }
+
+    /// Whether the transaction made it into a block, i.e. its latency is meaningful
+    pub fn is_included(&self) -> bool {
+        !matches!(self, TxStatus::Error | TxStatus::Cancelled)
+    }
+}
+
+impl std::fmt::Display for TxStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TxStatus::Success => write!(f, "SUCCESS"),
+            TxStatus::Reverted => write!(f, "FAILED"),
+            TxStatus::Unknown => write!(f, "UNKNOWN"),
+            TxStatus::Error => write!(f, "ERROR"),
+            TxStatus::Cancelled => write!(f, "CANCELLED"),
+        }
+    }
+}
+
+/// Where the time of a transaction went besides the submission round trip (`TxResult::send_time`)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhaseTimings {
+    /// Building the transaction: nonce, gas limit and fees
+    pub fill: Duration,
+    /// Signing with the local wallet
+    pub sign: Duration,
+    /// RLP-encoding the signed transaction
+    pub encode: Duration,
+    /// From handing the signed transaction to the RPC until the receipt is in hand
+    pub to_receipt: Duration,
+    /// From handing the transaction to the RPC until its block had the requested confirmations;
+    /// only set when more than one was requested
+    pub to_confirmations: Option<Duration>,
+    /// From handing the transaction to the RPC until its block was covered by the finality tag;
+    /// only set when a finality tag was requested
+    pub to_finality: Option<Duration>,
+}
+
+/// Inclusion measured against the chain rather than the local clock
+#[derive(Debug, Clone, Copy)]
+pub struct BlockTiming {
+    /// Timestamp of the including block, in seconds
+    pub block_timestamp: u64,
+    /// Block timestamp minus the local wall-clock time the transaction was sent, in milliseconds.
+    /// Can be negative: it includes the clock offset, and timestamps usually have one-second resolution.
+    pub block_minus_send_ms: f64,
+    /// Chain head when the transaction was sent, as last seen by the head tracker
+    pub head_at_send: Option<u64>,
+}
+
+/// Timing result of a single benchmarked transaction
+#[derive(Debug, Clone)]
+pub struct TxResult {
+    pub index: u64,
+    /// Sender wallet
+    pub wallet: Address,
+    pub nonce: u64,
+    /// Label of the endpoint the transaction was sent through
+    pub endpoint: String,
+    pub method: TxMethod,
+    pub tx_type: TxType,
+    /// Missing if the transaction failed before a receipt was received; the hash that landed if
+    /// the transaction was replaced
+    pub hash: Option<H256>,
+    /// HTTP round trip of the submission call; for the sync methods this includes inclusion
+    pub send_time: Duration,
+    pub confirm_time: Duration,
+    /// Client-side preparation and time-to-receipt; zero for phases a failed transaction never reached
+    pub phases: PhaseTimings,
+    /// Measured from the intended start time, so it includes `start_lag`
+    pub total_time: Duration,
+    /// How late the transaction started relative to its schedule (open-loop mode only)
+    pub start_lag: Duration,
+    pub block_number: Option<u64>,
+    pub gas_used: Option<U256>,
+    /// Set when block timing is enabled and the transaction was included
+    pub block_timing: Option<BlockTiming>,
+    /// Times the including block was reorged out while tracking confirmations
+    pub reorgs: u32,
+    /// Block the transaction ended up in after a reorg; `block_number` stays the original block
+    pub final_block_number: Option<u64>,
+    /// Extra latency caused by reorgs: from the first receipt until the receipt from the final block
+    pub reorg_delay: Option<Duration>,
+    /// Set when confirmation tracking gave up before reaching the requested depth or finality
+    pub tracking_timed_out: bool,
+    /// Gas price, or max fee per gas for EIP-1559, the transaction was first sent with
+    pub fee_per_gas: Option<U256>,
+    /// Priority fee per gas it was first sent with; EIP-1559 only
+    pub priority_fee: Option<U256>,
+    /// Priority fee level the transaction was assigned in a tip sweep
+    pub tip_level: Option<U256>,
+    /// Transactions sent with the same nonce while this one was stuck, in order
+    pub replacements: Vec<Replacement>,
+    /// `eth_getTransactionReceipt` calls made while confirming; `None` for the sync methods
+    pub receipt_calls: Option<u32>,
+    /// Connection-level timing of every JSON-RPC call the transaction made, in order;
+    /// empty unless the instrumented HTTP transport is used
+    pub http_calls: Vec<HttpTiming>,
+    /// When each observer endpoint saw the transaction and its receipt, measured from sending it
+    pub observations: Vec<Observation>,
+    /// Sent before the measured transactions and excluded from statistics
+    pub warmup: bool,
+    pub status: TxStatus,
+    pub error: Option<String>,
+}
+
+impl TxResult {
+    /// Blocks produced between the head at send time and the including block
+    pub fn blocks_since_head(&self) -> Option<u64> {
+        let head = self.block_timing?.head_at_send?;
+        Some(self.block_number?.saturating_sub(head))
+    }
+
+    /// Timing of the call that submitted the transaction, which need not be the first call: fees
+    /// may be looked up before it
+    pub fn submission_call(&self) -> Option<&HttpTiming> {
+        let rpc_method = self.method.submit_rpc_method();
+        self.http_calls.iter().find(|c| c.rpc_method == rpc_method)
+    }
+}
+
+/// Everything produced by a benchmark run
+#[derive(Debug)]
+pub struct Report {
+    pub metadata: RunMetadata,
+    /// All transactions, including failed ones: warm-up transactions first, then the measured ones,
+    /// each ordered by index
+    pub results: Vec<TxResult>,
+    /// Wall-clock time for the measured batch, not counting warm-up
+    pub elapsed: Duration,
+}
+
+impl Report {
+    /// Measured transactions, i.e. everything except warm-up
+    pub fn measured(&self) -> impl Iterator<Item = &TxResult> {
+        self.results.iter().filter(|r| !r.warmup)
+    }
+
+    /// Measured transactions that were actually included, i.e. the ones latency statistics are computed over
+    pub fn included(&self) -> impl Iterator<Item = &TxResult> {
+        self.measured().filter(|r| r.status.is_included())
+    }
+
+    pub fn send_stats(&self) -> Option<LatencyStats> {
+        self.stats_for(|r| r.send_time)
+    }
+
+    pub fn confirm_stats(&self) -> Option<LatencyStats> {
+        self.stats_for(|r| r.confirm_time)
+    }
+
+    pub fn total_stats(&self) -> Option<LatencyStats> {
+        self.stats_for(|r| r.total_time)
+    }
+
+    /// Included transactions per second over the whole batch, including time spent waiting on in-flight transactions
+    pub fn throughput(&self) -> f64 {
+        self.included().count() as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Writes every transaction record plus the run metadata to `path`
+    pub fn write(&self, format: OutputFormat, path: &Path) -> Result<()> {
+        export::write_results(format, path, &self.metadata, &self.results)
+    }
+
+    fn stats_for(&self, metric: impl Fn(&TxResult) -> Duration) -> Option<LatencyStats> {
+        let samples: Vec<Duration> = self.included().map(metric).collect();
+        LatencyStats::from_durations(&samples)
+    }
+
+    /// Send, confirm and total time statistics of the transactions sent through one endpoint
+    pub fn endpoint_stats(&self, endpoint: &str) -> Option<[LatencyStats; 3]> {
+        self.group_stats(|r| r.endpoint == endpoint)
+    }
+
+    /// Send, confirm and total time statistics of the transactions sent from one wallet
+    pub fn wallet_stats(&self, wallet: Address) -> Option<[LatencyStats; 3]> {
+        self.group_stats(|r| r.wallet == wallet)
+    }
+
+    /// Send, confirm and total time statistics of the transactions sent with one method
+    pub fn method_stats(&self, method: TxMethod) -> Option<[LatencyStats; 3]> {
+        self.group_stats(|r| r.method == method)
+    }
+
+    fn group_stats<'a>(&'a self, filter: impl Fn(&'a TxResult) -> bool) -> Option<[LatencyStats; 3]> {
+        let included: Vec<&TxResult> = self.included().filter(|r| filter(r)).collect();
+        let stats = |metric: fn(&TxResult) -> Duration| {
+            LatencyStats::from_durations(&included.iter().map(|r| metric(r)).collect::<Vec<_>>())
+        };
+        Some([stats(|r| r.send_time)?, stats(|r| r.confirm_time)?, stats(|r| r.total_time)?])
+    }
+
+    /// Prints the per-transaction table, latency statistics and throughput
+    pub fn print_summary(&self) {
+        println!("\n===== SUMMARY =====");
+        println!("Total time for all transactions: {:?}", self.elapsed);
+        println!();
+
+        let warmup: Vec<&TxResult> = self.results.iter().filter(|r| r.warmup).collect();
+        if !warmup.is_empty() {
+            println!("Warm-up Transactions (excluded from statistics):");
+            print_results_table(warmup);
+            println!();
+        }
+
+        println!("Individual Transaction Results:");
+        print_results_table(self.measured());
+
+        // Calculate percentiles and distributions with microsecond resolution,
+        // only over transactions that were actually included
+        let (Some(send_stats), Some(confirm_stats), Some(total_stats)) =
+            (self.send_stats(), self.confirm_stats(), self.total_stats())
+        else {
+            return;
+        };
+        let series = [
+            ("Send time:", &send_stats),
+            ("Confirm time:", &confirm_stats),
+            ("Total time:", &total_stats),
+        ];
+
+        println!("\nLATENCY STATISTICS (ms):");
+        print!("{:<13} {:>10} {:>10} {:>10}", "", "MIN", "AVG", "STDDEV");
+        for (label, _) in PERCENTILES {
+            print!(" {:>10}", label);
+        }
+        println!(" {:>10}", "MAX");
+        println!("{}", "-".repeat(13 + 11 * (PERCENTILES.len() + 4)));
+        for (label, stats) in series {
+            print!("{:<13} {:>10} {:>10} {:>10}", label, fmt_ms(stats.min()), fmt_ms(stats.mean()), fmt_ms(stats.stddev()));
+            for (_, quantile) in PERCENTILES {
+                print!(" {:>10}", fmt_ms(stats.percentile(quantile)));
+            }
+            println!(" {:>10}", fmt_ms(stats.max()));
+        }
+
+        for (label, stats) in series {
+            // Sync methods have no separate confirm phase, so skip an all-zero distribution
+            if stats.max().is_zero() {
+                continue;
+            }
+            println!("\n{} distribution ({} samples, ms):", label.trim_end_matches(':'), stats.count());
+            print!("{}", stats.render_histogram(10, 40));
+        }
+
+        if self.metadata.endpoints.len() > 1 {
+            self.print_endpoint_comparison();
+        }
+
+        if self.metadata.wallets.len() > 1 {
+            self.print_wallet_comparison();
+        }
+
+        if self.metadata.methods.len() > 1 {
+            self.print_method_comparison();
+        }
+
+        if !self.metadata.tip_levels_wei.is_empty() {
+            self.print_tip_sweep();
+        }
+
+        if !self.metadata.observers.is_empty() {
+            self.print_propagation();
+        }
+
+        if self
+            .included()
+            .any(|r| r.phases.to_confirmations.is_some() || r.phases.to_finality.is_some() || r.tracking_timed_out)
+        {
+            self.print_confirmation_depth();
+        }
+
+        if self.metadata.confirmations.is_some() || self.metadata.finality.is_some() {
+            self.print_reorgs();
+        }
+
+        if self.measured().any(|r| !r.replacements.is_empty()) {
+            self.print_replacements();
+        }
+
+        if self.included().any(|r| r.block_timing.is_some()) {
+            self.print_block_timing();
+        }
+
+        if self.results.iter().any(|r| !r.http_calls.is_empty()) {
+            self.print_connection_timing();
+        }
+
+        let receipt_calls: Vec<u32> = self.included().filter_map(|r| r.receipt_calls).collect();
+        if let Some(&max_calls) = receipt_calls.iter().max() {
+            let total_calls: u32 = receipt_calls.iter().sum();
+            println!("\nRECEIPT POLLING ({}): {} eth_getTransactionReceipt calls for {} confirmations (avg {:.2}, max {})",
+                self.metadata.poll_strategy.as_deref().unwrap_or("-"), total_calls, receipt_calls.len(),
+                total_calls as f64 / receipt_calls.len() as f64, max_calls);
+        }
+
+        let gas_used: Vec<u64> = self.included().filter_map(|r| r.gas_used).map(|g| g.as_u64()).collect();
+        if let (Some(&min), Some(&max)) = (gas_used.iter().min(), gas_used.iter().max()) {
+            let avg = gas_used.iter().sum::<u64>() as f64 / gas_used.len() as f64;
+            println!("\nGAS ({}): limit {}, used avg {:.0} ({:.1}% of limit), min {}, max {}",
+                self.metadata.workload, self.metadata.gas_limit, avg,
+                avg / self.metadata.gas_limit.max(1) as f64 * 100.0, min, max);
+        }
+
+        let fees: Vec<U256> = self.included().filter_map(|r| r.fee_per_gas).collect();
+        if let Some((min, avg, max)) = fee_range(&fees) {
+            print!("\nFEES ({}): fee per gas avg {}, min {}, max {}",
+                self.metadata.gas_strategy, fmt_gwei(avg), fmt_gwei(min), fmt_gwei(max));
+            let tips: Vec<U256> = self.included().filter_map(|r| r.priority_fee).collect();
+            match fee_range(&tips) {
+                Some((min, avg, max)) => println!("; priority fee avg {}, min {}, max {}",
+                    fmt_gwei(avg), fmt_gwei(min), fmt_gwei(max)),
+                None => println!(),
+            }
+        }
+
+        let included = self.included().count();
+        println!("\nTHROUGHPUT: {:.2} tx/s ({} confirmed, {} failed)",
+            self.throughput(), included, self.measured().count() - included);
+
+        match self.metadata.rate {
+            Some(rate) => {
+                let max_lag = self.included().map(|r| r.start_lag).max().unwrap_or_default();
+                println!("Target rate: {:.2} tx/s, max start lag: {} ms", rate, fmt_ms(max_lag));
+                println!("\nSUMMARY: {} transactions sent open-loop and confirmed in {} ms (p50: {} ms, p99: {} ms, max: {} ms, measured from intended send time)",
+                    included, self.elapsed.as_millis(), fmt_ms(total_stats.percentile(0.5)),
+                    fmt_ms(total_stats.percentile(0.99)), fmt_ms(total_stats.max()));
+            }
+            None => {
+                println!("\nSUMMARY: {} transactions sent and confirmed with concurrency {} in {} ms (p50: {} ms, p99: {} ms, max: {} ms)",
+                    included, self.metadata.concurrency, self.elapsed.as_millis(), fmt_ms(total_stats.percentile(0.5)),
+                    fmt_ms(total_stats.percentile(0.99)), fmt_ms(total_stats.max()));
+            }
+        }
+    }
+
+    /// Prints send, confirm and total time percentiles side by side for every endpoint
+    fn print_endpoint_comparison(&self) {
+        let endpoints = self.metadata.endpoints.iter().map(|e| (e.label.clone(), e.label.as_str()));
+        self.print_comparison("ENDPOINT", 24, endpoints, |r| r.endpoint.as_str());
+    }
+
+    /// Prints send, confirm and total time percentiles side by side for every sender wallet
+    fn print_wallet_comparison(&self) {
+        let wallets = self.metadata.wallets.iter().map(|&wallet| (format!("{:?}", wallet), wallet));
+        self.print_comparison("WALLET", 42, wallets, |r| r.wallet);
+    }
+
+    /// Prints send, confirm and total time percentiles side by side for every group of
+    /// transactions, one row per `(label, key)` of `groups`, where `key` tells which group a
+    /// transaction belongs to. `column` heads the labels, which are `width` characters wide.
+    fn print_comparison<'a, K: PartialEq>(
+        &'a self,
+        column: &str,
+        width: usize,
+        groups: impl Iterator<Item = (String, K)>,
+        key: impl Fn(&'a TxResult) -> K,
+    ) {
+        println!("\n{} COMPARISON (ms):", column);
+        print!("{:<width$} {:>5} {:>6}", column, "N", "FAILED");
+        for metric in ["SEND", "CONFIRM", "TOTAL"] {
+            for (label, _) in QUANTILES {
+                print!(" {:>11}", format!("{} {}", metric, label));
+            }
+        }
+        println!();
+        println!("{}", "-".repeat(width + 13 + 12 * 3 * QUANTILES.len()));
+
+        for (label, group) in groups {
+            let sent = self.measured().filter(|&r| key(r) == group).count();
+            let included = self.included().filter(|&r| key(r) == group).count();
+            print!("{:<width$} {:>5} {:>6}", label, sent, sent - included);
+            match self.group_stats(|r| key(r) == group) {
+                Some(stats) => {
+                    for stats in &stats {
+                        for (_, quantile) in QUANTILES {
+                            print!(" {:>11}", fmt_ms(stats.percentile(quantile)));
+                        }
+                    }
+                    println!();
+                }
+                None => println!(" {:>11}", "-"),
+            }
+        }
+    }
+
+    /// Prints total time per method and how much faster or slower each method is than the first
+    /// (async), with a 95% confidence interval for the difference of the means
+    fn print_method_comparison(&self) {
+        let methods = &self.metadata.methods;
+        let baseline = methods[0];
+        let baseline_total = self.method_stats(baseline).map(|[_, _, total]| total);
+
+        println!("\nMETHOD COMPARISON (total time, ms; difference of means vs {} with 95% CI):", baseline);
+        println!("{:<8} {:>5} {:>6} {:>10} {:>10} {:>10} {:>10} {:>24}",
+                 "METHOD", "N", "FAILED", "MEAN", "P50", "P99", "DIFF", "95% CI");
+        println!("{}", "-".repeat(90));
+        for &method in methods {
+            let sent = self.measured().filter(|r| r.method == method).count();
+            let included = self.included().filter(|r| r.method == method).count();
+            print!("{:<8} {:>5} {:>6}", method.to_string(), sent, sent - included);
+            let Some([_, _, total]) = self.method_stats(method) else {
+                println!(" {:>10}", "-");
+                continue;
+            };
+            print!(" {:>10} {:>10} {:>10}",
+                   fmt_ms(total.mean()), fmt_ms(total.percentile(0.5)), fmt_ms(total.percentile(0.99)));
+            let difference = baseline_total.as_ref().and_then(|base| total.mean_difference(base));
+            match difference {
+                Some(d) if method != baseline => println!(" {:>+10.3} {:>24}{}",
+                    d.diff_ms, format!("[{:+.3}, {:+.3}]", d.low_ms, d.high_ms),
+                    if d.is_significant() { "" } else { "  (not significant)" }),
+                _ => println!(" {:>10} {:>24}", "-", "-"),
+            }
+        }
+    }
+
+    /// Prints total time and blocks from the head at send to inclusion for every tip level of a
+    /// sweep, with a bar of the p50 total time, and the cheapest tip that got every transaction
+    /// into the next block
+    fn print_tip_sweep(&self) {
+        let mut levels: Vec<U256> = self.measured().filter_map(|r| r.tip_level).collect();
+        levels.sort();
+        levels.dedup();
+        let p50s: Vec<Option<Duration>> = levels
+            .iter()
+            .map(|&level| self.group_stats(|r| r.tip_level == Some(level)).map(|[_, _, total]| total.percentile(0.5)))
+            .collect();
+        let slowest = p50s.iter().flatten().max().copied().unwrap_or_default();
+
+        println!("\nTIP SWEEP (total time, ms; blocks from head at send to inclusion):");
+        println!("{:<14} {:>5} {:>6} {:>10} {:>10} {:>10} {:>7} {:>7} {:>10}  P50 TOTAL",
+                 "PRIORITY FEE", "N", "FAILED", "P50", "P90", "MAX", "BLK P50", "BLK MAX", "NEXT BLOCK");
+        println!("{}", "-".repeat(129));
+        let mut cheapest_next_block = None;
+        for (&level, p50) in levels.iter().zip(&p50s) {
+            let sent: Vec<&TxResult> = self.measured().filter(|r| r.tip_level == Some(level)).collect();
+            let included = sent.iter().filter(|r| r.status.is_included()).count();
+            print!("{:<14} {:>5} {:>6}", fmt_gwei(level), sent.len(), sent.len() - included);
+            let Some([_, _, total]) = self.group_stats(|r| r.tip_level == Some(level)) else {
+                println!(" {:>10}", "-");
+                continue;
+            };
+            let mut blocks: Vec<u64> = sent
+                .iter()
+                .filter(|r| r.status.is_included())
+                .filter_map(|r| r.blocks_since_head())
+                .collect();
+            blocks.sort_unstable();
+            let next_block = blocks.iter().filter(|&&b| b <= 1).count();
+            if next_block == sent.len() && cheapest_next_block.is_none() {
+                cheapest_next_block = Some(level);
+            }
+            let bar_len = if slowest.is_zero() {
+                0
+            } else {
+                (p50.unwrap_or_default().as_secs_f64() / slowest.as_secs_f64() * 40.0).ceil() as usize
+            };
+            println!(" {:>10} {:>10} {:>10} {:>7} {:>7} {:>9.0}%  {}",
+                     fmt_ms(total.percentile(0.5)), fmt_ms(total.percentile(0.9)), fmt_ms(total.max()),
+                     blocks.get(blocks.len() / 2).map(|b| b.to_string()).unwrap_or_else(|| "-".to_string()),
+                     blocks.last().map(|b| b.to_string()).unwrap_or_else(|| "-".to_string()),
+                     next_block as f64 / sent.len() as f64 * 100.0, "#".repeat(bar_len));
+        }
+        match cheapest_next_block {
+            Some(level) => println!("Cheapest tip with every transaction in the next block: {}", fmt_gwei(level)),
+            None => println!("No tip got every transaction into the next block"),
+        }
+    }
+
+    /// Prints how long included transactions took to show up on each observer, in its mempool
+    /// (or a block) and as a receipt, measured from sending them
+    fn print_propagation(&self) {
+        println!("\nPROPAGATION (ms from send):");
+        println!("{:<24} {:>6} {:>10} {:>10} {:>10} {:>8} {:>10} {:>10} {:>10}",
+                 "OBSERVER", "SEEN", "SEEN P50", "SEEN P90", "SEEN MAX", "RECEIPT", "RCPT P50", "RCPT P90", "RCPT MAX");
+        println!("{}", "-".repeat(104));
+
+        let sent = self.included().count();
+        for observer in &self.metadata.observers {
+            let observations: Vec<&Observation> = self
+                .included()
+                .flat_map(|r| &r.observations)
+                .filter(|o| o.observer == observer.label)
+                .collect();
+            let seen: Vec<Duration> = observations.iter().filter_map(|o| o.seen).collect();
+            let receipts: Vec<Duration> = observations.iter().filter_map(|o| o.receipt).collect();
+
+            print!("{:<24} {:>6}", observer.label, format!("{}/{}", seen.len(), sent));
+            print_quantiles(LatencyStats::from_durations(&seen));
+            print!(" {:>8}", format!("{}/{}", receipts.len(), sent));
+            print_quantiles(LatencyStats::from_durations(&receipts));
+            println!();
+        }
+    }
+
+    /// Prints time to the first receipt next to time to the requested depth and finality, all
+    /// measured from handing the transaction to the RPC
+    fn print_confirmation_depth(&self) {
+        let confirmations = self.metadata.confirmations.map(|n| format!("{} confirmations:", n));
+        let finality = self.metadata.finality.map(|f| format!("Finality ({}):", f));
+        let rows = [
+            (Some("First receipt:".to_string()), self.stats_for(|r| r.phases.to_receipt)),
+            (confirmations, self.optional_stats(|r| r.phases.to_confirmations)),
+            (finality, self.optional_stats(|r| r.phases.to_finality)),
+        ];
+
+        println!("\nCONFIRMATION DEPTH (ms from send):");
+        println!("{:<22} {:>6} {:>10} {:>10} {:>10} {:>10} {:>10}", "", "N", "MIN", "P50", "P90", "P99", "MAX");
+        println!("{}", "-".repeat(85));
+        for (label, stats) in rows {
+            let (Some(label), Some(stats)) = (label, stats) else {
+                continue;
+            };
+            println!("{:<22} {:>6} {:>10} {:>10} {:>10} {:>10} {:>10}", label, stats.count(),
+                     fmt_ms(stats.min()), fmt_ms(stats.percentile(0.5)), fmt_ms(stats.percentile(0.9)),
+                     fmt_ms(stats.percentile(0.99)), fmt_ms(stats.max()));
+        }
+
+        let timed_out = self.included().filter(|r| r.tracking_timed_out).count();
+        if timed_out > 0 {
+            let timeout = self.metadata.confirmation_timeout_ms.unwrap_or_default() / 1000.0;
+            println!("Timed out: {} transactions didn't reach the target within {}s and are missing from the rows they didn't reach",
+                     timed_out, timeout);
+        }
+    }
+
+    /// Prints the transactions whose block was reorged out while tracking confirmations, and how
+    /// much later they were included for good
+    fn print_reorgs(&self) {
+        let reorged: Vec<&TxResult> = self.included().filter(|r| r.reorgs > 0).collect();
+        if reorged.is_empty() {
+            println!("\nREORGS: none detected while tracking confirmations");
+            return;
+        }
+
+        let total: u32 = reorged.iter().map(|r| r.reorgs).sum();
+        println!("\nREORGS: {} transactions reorged out {} times", reorged.len(), total);
+        println!("{:<5} {:>7} {:>14} {:>14} {:>14}", "TX#", "REORGS", "ORIGINAL BLOCK", "FINAL BLOCK", "EXTRA (ms)");
+        println!("{}", "-".repeat(58));
+        for r in reorged {
+            println!("{:<5} {:>7} {:>14} {:>14} {:>14}",
+                     tx_label(r),
+                     r.reorgs,
+                     r.block_number.map(|b| b.to_string()).unwrap_or_else(|| "-".to_string()),
+                     r.final_block_number.map(|b| b.to_string()).unwrap_or_else(|| "pending".to_string()),
+                     r.reorg_delay.map(fmt_ms).unwrap_or_else(|| "-".to_string()));
+        }
+    }
+
+    /// Prints the transactions that got stuck and were replaced, and which of their transactions landed
+    fn print_replacements(&self) {
+        let replaced: Vec<&TxResult> = self.measured().filter(|r| !r.replacements.is_empty()).collect();
+        let total: usize = replaced.iter().map(|r| r.replacements.len()).sum();
+        println!("\nSTUCK TRANSACTIONS: {} transactions replaced {} times ({})",
+                 replaced.len(), total, self.metadata.stuck_policy.as_deref().unwrap_or("-"));
+        println!("{:<5} {:<8} {:>8} {:<14} {:>15} {:>12}  {:<66}",
+                 "TX#", "NONCE", "REPLACED", "LANDED", "LAST SENT (ms)", "TOTAL (ms)", "ORIGINAL HASH");
+        println!("{}", "-".repeat(134));
+        for r in replaced {
+            let original = r.replacements[0].replaces;
+            let landed = match r.hash {
+                None => "-".to_string(),
+                Some(hash) if hash == original => "original".to_string(),
+                Some(hash) => match r.replacements.iter().position(|p| p.hash == hash) {
+                    Some(i) => format!("{} #{}", r.replacements[i].action, i + 1),
+                    None => "-".to_string(),
+                },
+            };
+            let last_sent = r.replacements.last().map(|p| p.after).unwrap_or_default();
+            println!("{:<5} {:<8} {:>8} {:<14} {:>15} {:>12}  {:?}",
+                     tx_label(r),
+                     r.nonce,
+                     r.replacements.len(),
+                     landed,
+                     fmt_ms(last_sent),
+                     fmt_ms(r.total_time),
+                     original);
+        }
+    }
+
+    /// Statistics of a metric only some included transactions have
+    fn optional_stats(&self, metric: impl Fn(&TxResult) -> Option<Duration>) -> Option<LatencyStats> {
+        let samples: Vec<Duration> = self.included().filter_map(metric).collect();
+        LatencyStats::from_durations(&samples)
+    }
+
+    /// Prints inclusion latency measured with block timestamps, corrected for the estimated clock
+    /// offset, and how many blocks it took from the head at send time
+    fn print_block_timing(&self) {
+        let mut raw: Vec<f64> = self
+            .included()
+            .filter_map(|r| r.block_timing)
+            .map(|t| t.block_minus_send_ms)
+            .collect();
+        raw.sort_by(f64::total_cmp);
+
+        println!("\nBLOCK TIMING:");
+        let offset = self.metadata.clock_offset_ms;
+        match (offset, self.metadata.clock_offset_blocks) {
+            (Some(offset), Some(blocks)) => println!(
+                "Clock offset (local - chain): {:+.3} ms, estimated from {} blocks (includes block delivery time; timestamps have 1 s resolution)",
+                offset, blocks),
+            _ => println!("Clock offset (local - chain): not enough blocks seen to estimate"),
+        }
+
+        println!("{:<34} {:>10} {:>10} {:>10} {:>10}", "(ms)", "MIN", "P50", "P90", "MAX");
+        println!("{}", "-".repeat(78));
+        let print_row = |label: &str, shift: f64| {
+            println!("{:<34} {:>10.3} {:>10.3} {:>10.3} {:>10.3}", label,
+                     raw[0] + shift, quantile(&raw, 0.5) + shift, quantile(&raw, 0.9) + shift, raw[raw.len() - 1] + shift);
+        };
+        print_row("Block timestamp - send time", 0.0);
+        // A local clock ahead of the chain makes the raw difference too small by the offset
+        if let Some(offset) = offset {
+            print_row("Corrected for clock offset", offset);
+        }
+
+        let mut blocks: Vec<u64> = self.included().filter_map(|r| r.blocks_since_head()).collect();
+        if !blocks.is_empty() {
+            blocks.sort_unstable();
+            let mut counts: Vec<(u64, usize)> = Vec::new();
+            for b in blocks {
+                match counts.last_mut() {
+                    Some((last, count)) if *last == b => *count += 1,
+                    _ => counts.push((b, 1)),
+                }
+            }
+            let counts: Vec<String> = counts.iter().map(|(b, n)| format!("{}: {} tx", b, n)).collect();
+            println!("Blocks from head at send to inclusion: {}", counts.join(", "));
+        }
+    }
+
+    /// Prints connection setup and time to first byte of each transaction's submission call,
+    /// so slow connection setup can be told apart from chain latency
+    fn print_connection_timing(&self) {
+        println!("\nCONNECTION TIMING (ms, submission call):");
+        println!("{:<5} {:>6} {:>9} {:>7} {:>9} {:>9} {:>9} {:>9} {:>9}",
+                 "TX#", "CALLS", "NEW CONN", "REUSED", "DNS", "CONNECT", "TLS", "WRITE", "TTFB");
+        println!("{}", "-".repeat(83));
+        for r in &self.results {
+            let Some(send_call) = r.submission_call() else {
+                continue;
+            };
+            let new_connections = r.http_calls.iter().filter(|c| !c.reused).count();
+            println!("{:<5} {:>6} {:>9} {:>7} {:>9} {:>9} {:>9} {:>9} {:>9}",
+                     tx_label(r),
+                     r.http_calls.len(),
+                     new_connections,
+                     if send_call.reused { "yes" } else { "no" },
+                     fmt_ms(send_call.dns),
+                     fmt_ms(send_call.tcp_connect),
+                     fmt_ms(send_call.tls_handshake),
+                     fmt_ms(send_call.write),
+                     fmt_ms(send_call.ttfb));
+        }
+    }
+}
+
+/// Prints one row per transaction with its phase timings and hash, or error if it failed
+fn print_results_table<'a>(results: impl IntoIterator<Item = &'a TxResult>) {
+    println!("{:<5} {:<8} {:<6} {:<9} {:<10} {:<9} {:<9} {:<9} {:<12} {:<12} {:<12} {:<12} {:<64}",
+             "TX#", "NONCE", "METHOD", "STATUS", "LAG (ms)", "FILL (ms)", "SIGN (ms)", "RLP (ms)", "SEND (ms)", "CONFIRM (ms)", "RECEIPT (ms)", "TOTAL (ms)", "HASH");
+    println!("{}", "-".repeat(191));
+
+    for r in results {
+        let hash = match (r.hash, &r.error) {
+            (Some(hash), _) => format!("{:?}", hash),
+            (None, Some(error)) => error.clone(),
+            (None, None) => "-".to_string(),
+        };
+        println!("{:<5} {:<8} {:<6} {:<9} {:<10} {:<9} {:<9} {:<9} {:<12} {:<12} {:<12} {:<12} {:<64}",
+                 tx_label(r),
+                 r.nonce,
+                 r.method.to_string(),
+                 r.status,
+                 fmt_ms(r.start_lag),
+                 fmt_ms(r.phases.fill),
+                 fmt_ms(r.phases.sign),
+                 fmt_ms(r.phases.encode),
+                 fmt_ms(r.send_time),
+                 fmt_ms(r.confirm_time),
+                 fmt_ms(r.phases.to_receipt),
+                 fmt_ms(r.total_time),
+                 hash);
+    }
+}
+
+/// Smallest, mean and largest of some fees, or `None` if there are none
+fn fee_range(fees: &[U256]) -> Option<(U256, U256, U256)> {
+    let (&min, &max) = (fees.iter().min()?, fees.iter().max()?);
+    let avg = fees.iter().fold(U256::zero(), |sum, fee| sum + fee) / fees.len();
+    Some((min, avg, max))
+}
+
+/// Nearest-rank quantile of sorted, non-empty samples
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Prints the p50, p90 and max columns of the propagation table
+fn print_quantiles(stats: Option<LatencyStats>) {
+    match stats {
+        Some(stats) => print!(" {:>10} {:>10} {:>10}",
+                              fmt_ms(stats.percentile(0.5)), fmt_ms(stats.percentile(0.9)), fmt_ms(stats.max())),
+        None => print!(" {:>10} {:>10} {:>10}", "-", "-", "-"),
+    }
+}
+
+/// 1-based transaction number, prefixed with `W` for warm-up transactions
+fn tx_label(r: &TxResult) -> String {
+    if r.warmup {
+        format!("W{}", r.index + 1)
+    } else {
+        (r.index + 1).to_string()
+    }
+}
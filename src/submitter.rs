@@ -0,0 +1,648 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use ethers::{
+    middleware::Middleware,
+    signers::Signer,
+    types::{
+        transaction::{eip1559::Eip1559TransactionRequest, eip2718::TypedTransaction, eip2930::AccessList},
+        Bytes, TransactionReceipt, TransactionRequest, H256, U256,
+    },
+    utils::keccak256,
+};
+use futures::future;
+use log::{info, warn};
+use serde::Serialize;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::time::sleep;
+
+use crate::blob::{BlobProofs, BlobSidecar, BlobTransaction, BLOB_GAS_PER_BLOB};
+use crate::block_watcher::BlockWatcher;
+use crate::confirmation::{self, ConfirmationOutcome, ConfirmationTarget};
+use crate::gas::{self, GasStrategy, DEFAULT_PRIORITY_FEE};
+use crate::middleware::realtime_transaction;
+use crate::middleware::sync_submit::{SyncSubmit, SyncSubmitMiddleware};
+use crate::middleware::sync_transaction;
+use crate::observer::{Observer, PendingObservations};
+use crate::replacement::{Replacement, ReplacementChain, StuckPolicy};
+use crate::report::{PhaseTimings, TxStatus};
+use crate::workload::Workload;
+use crate::Client;
+
+/// Transaction submission method
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxMethod {
+    /// `eth_sendRawTransaction` followed by polling `eth_getTransactionReceipt`
+    Async,
+    /// `eth_sendRawTransactionSync`, which returns the receipt directly
+    Rise,
+    /// `realtime_sendRawTransaction`, which returns the receipt directly
+    Mega,
+}
+
+impl TxMethod {
+    /// The sync-submit RPC method this submission method uses, or `None` for async
+    pub fn sync_rpc_method(&self) -> Option<&'static str> {
+        match self {
+            TxMethod::Async => None,
+            TxMethod::Rise => Some(sync_transaction::METHOD),
+            TxMethod::Mega => Some(realtime_transaction::METHOD),
+        }
+    }
+
+    /// The RPC method that submits the transaction
+    pub fn submit_rpc_method(&self) -> &'static str {
+        self.sync_rpc_method().unwrap_or("eth_sendRawTransaction")
+    }
+}
+
+impl std::fmt::Display for TxMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TxMethod::Async => write!(f, "async"),
+            TxMethod::Rise => write!(f, "rise"),
+            TxMethod::Mega => write!(f, "mega"),
+        }
+    }
+}
+
+/// EIP-2718 transaction type, used for every submission method alike
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize)]
+pub enum TxType {
+    /// Type 0: pre-EIP-2718 transaction with a single gas price
+    #[value(name = "legacy")]
+    #[serde(rename = "legacy")]
+    Legacy,
+    /// Type 1: gas price plus an (empty) access list
+    #[value(name = "2930")]
+    #[serde(rename = "2930")]
+    Eip2930,
+    /// Type 2: max fee and priority fee per gas
+    #[default]
+    #[value(name = "1559")]
+    #[serde(rename = "1559")]
+    Eip1559,
+    /// Type 3: blob-carrying transaction
+    #[value(name = "4844")]
+    #[serde(rename = "4844")]
+    Eip4844,
+}
+
+impl std::fmt::Display for TxType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TxType::Legacy => write!(f, "legacy"),
+            TxType::Eip2930 => write!(f, "2930"),
+            TxType::Eip1559 => write!(f, "1559"),
+            TxType::Eip4844 => write!(f, "4844"),
+        }
+    }
+}
+
+/// How the async method polls `eth_getTransactionReceipt` while waiting for inclusion
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PollStrategy {
+    /// Sleep a fixed interval between polls
+    Fixed(Duration),
+    /// Sleep `initial` after the first miss and double it after every further miss, up to `max`
+    Backoff { initial: Duration, max: Duration },
+    /// Poll again as soon as the previous call returns
+    Hot,
+}
+
+impl PollStrategy {
+    /// How long to sleep after `misses` consecutive polls without a receipt
+    pub(crate) fn delay(&self, misses: u32) -> Duration {
+        match *self {
+            PollStrategy::Fixed(interval) => interval,
+            PollStrategy::Backoff { initial, max } => {
+                initial.saturating_mul(2u32.saturating_pow(misses.saturating_sub(1))).min(max)
+            }
+            PollStrategy::Hot => Duration::ZERO,
+        }
+    }
+}
+
+impl Default for PollStrategy {
+    fn default() -> Self {
+        PollStrategy::Fixed(Duration::from_millis(100))
+    }
+}
+
+impl std::fmt::Display for PollStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PollStrategy::Fixed(interval) => write!(f, "fixed {:?}", interval),
+            PollStrategy::Backoff { initial, max } => write!(f, "backoff {:?}..{:?}", initial, max),
+            PollStrategy::Hot => write!(f, "hot"),
+        }
+    }
+}
+
+/// A confirmed transaction and how long each phase took
+#[derive(Debug)]
+pub struct Submission {
+    pub receipt: TransactionReceipt,
+    /// Wall-clock time the signed transaction was handed to the RPC
+    pub sent_at: SystemTime,
+    /// HTTP round trip of the submission call; for the sync methods this includes inclusion
+    pub send_time: Duration,
+    /// Always zero for the sync methods, which have no separate confirm phase
+    pub confirm_time: Duration,
+    /// Client-side preparation of the transaction, and time from submission to receipt
+    pub phases: PhaseTimings,
+    /// `eth_getTransactionReceipt` calls made while confirming; `None` for the sync methods
+    pub receipt_calls: Option<u32>,
+    /// Observers watching for the transaction since it was sent; empty without observers
+    pub observations: PendingObservations,
+    /// Gas price, or max fee per gas for EIP-1559, the transaction was first sent with
+    pub fee_per_gas: U256,
+    /// Priority fee per gas it was first sent with; EIP-1559 only
+    pub priority_fee: Option<U256>,
+    /// Transactions sent in place of this one while it was stuck, in order; the receipt is from
+    /// whichever landed
+    pub replacements: Vec<Replacement>,
+    /// Whether the transaction that landed is a cancelling self-transfer
+    pub cancelled: bool,
+}
+
+/// Sends the workload's transactions with one of the three submission methods and waits for the receipt
+#[derive(Debug)]
+pub struct Submitter {
+    client: Arc<Client>,
+    /// Set for the sync methods, which submit through a sync-submit RPC instead of polling
+    sync_submitter: Option<SyncSubmitMiddleware<Arc<Client>>>,
+    /// Set when async confirmations come from a `newHeads` subscription instead of polling
+    block_watcher: Option<Arc<BlockWatcher>>,
+    /// Other endpoints watched for the transaction to measure propagation
+    observers: Vec<Arc<Observer>>,
+    poll_strategy: PollStrategy,
+    confirmation_target: ConfirmationTarget,
+    /// Set when async transactions without a receipt are rebroadcast, sped up or cancelled
+    stuck_policy: Option<StuckPolicy>,
+    workload: Workload,
+    gas_limit: u64,
+    tx_type: TxType,
+    /// Blob attached to every transaction of type 4844
+    blob_sidecar: Option<Arc<BlobSidecar>>,
+    method: TxMethod,
+    /// Evaluated for every transaction
+    gas_strategy: GasStrategy,
+    /// Gas price chosen at setup, for transactions outside the benchmark such as nonce gap fillers
+    gas_price: U256,
+}
+
+impl Submitter {
+    /// Create a submitter for `method` that pays `gas_price` (with a 1 gwei priority fee for EIP-1559)
+    /// unless given a gas strategy; the client's signer must already carry the chain ID
+    pub fn new(client: Arc<Client>, method: TxMethod, gas_price: U256) -> Self {
+        Self {
+            sync_submitter: method
+                .sync_rpc_method()
+                .map(|rpc_method| SyncSubmitMiddleware::new(client.clone(), rpc_method)),
+            client,
+            block_watcher: None,
+            observers: Vec::new(),
+            poll_strategy: PollStrategy::default(),
+            confirmation_target: ConfirmationTarget::default(),
+            stuck_policy: None,
+            workload: Workload::SelfTransfer,
+            gas_limit: 21000,
+            tx_type: TxType::default(),
+            blob_sidecar: None,
+            method,
+            gas_strategy: GasStrategy::Fixed { gas_price, priority_fee: U256::from(DEFAULT_PRIORITY_FEE) },
+            gas_price,
+        }
+    }
+
+    /// Detect async confirmations with `block_watcher` rather than polling for the receipt
+    pub fn with_block_watcher(mut self, block_watcher: Arc<BlockWatcher>) -> Self {
+        self.block_watcher = Some(block_watcher);
+        self
+    }
+
+    /// Watch `observers` for every transaction from the moment it is sent
+    pub fn with_observers(mut self, observers: Vec<Arc<Observer>>) -> Self {
+        self.observers = observers;
+        self
+    }
+
+    /// How the async method polls for receipts (default: every 100 ms)
+    pub fn with_poll_strategy(mut self, poll_strategy: PollStrategy) -> Self {
+        self.poll_strategy = poll_strategy;
+        self
+    }
+
+    /// Keep tracking included transactions until they are deep enough or final (default: first receipt)
+    pub fn with_confirmation_target(mut self, confirmation_target: ConfirmationTarget) -> Self {
+        self.confirmation_target = confirmation_target;
+        self
+    }
+
+    /// Rebroadcast, speed up or cancel async transactions that have no receipt after the policy's
+    /// timeout (default: wait for the receipt indefinitely)
+    pub fn with_stuck_policy(mut self, stuck_policy: StuckPolicy) -> Self {
+        self.stuck_policy = Some(stuck_policy);
+        self
+    }
+
+    /// What every transaction does and its gas limit (default: self-transfers with 21000 gas)
+    pub fn with_workload(mut self, workload: Workload, gas_limit: u64) -> Self {
+        self.workload = workload;
+        self.gas_limit = gas_limit;
+        self
+    }
+
+    /// Blob carried by blob transactions; required for transactions of type 4844
+    pub fn with_blob_sidecar(mut self, blob_sidecar: Arc<BlobSidecar>) -> Self {
+        self.blob_sidecar = Some(blob_sidecar);
+        self
+    }
+
+    /// How the fees of every transaction are chosen; evaluated each time a transaction is built
+    /// (default: the fixed gas price given to [`Submitter::new`])
+    pub fn with_gas_strategy(mut self, gas_strategy: GasStrategy) -> Self {
+        self.gas_strategy = gas_strategy;
+        self
+    }
+
+    /// Transaction type to build, whatever the method (default: EIP-1559)
+    pub fn with_tx_type(mut self, tx_type: TxType) -> Self {
+        self.tx_type = tx_type;
+        self
+    }
+
+    pub fn client(&self) -> &Arc<Client> {
+        &self.client
+    }
+
+    pub fn method(&self) -> TxMethod {
+        self.method
+    }
+
+    pub fn tx_type(&self) -> TxType {
+        self.tx_type
+    }
+
+    pub fn blob_proofs(&self) -> Option<BlobProofs> {
+        self.blob_sidecar.as_ref().map(|blob_sidecar| blob_sidecar.kind())
+    }
+
+    /// Gas price chosen at setup; benchmark transactions use the gas strategy instead
+    pub fn gas_price(&self) -> U256 {
+        self.gas_price
+    }
+
+    pub fn gas_strategy(&self) -> GasStrategy {
+        self.gas_strategy
+    }
+
+    pub fn workload(&self) -> &Workload {
+        &self.workload
+    }
+
+    pub fn gas_limit(&self) -> u64 {
+        self.gas_limit
+    }
+
+    pub fn poll_strategy(&self) -> PollStrategy {
+        self.poll_strategy
+    }
+
+    pub fn confirmation_target(&self) -> ConfirmationTarget {
+        self.confirmation_target
+    }
+
+    pub fn stuck_policy(&self) -> Option<StuckPolicy> {
+        self.stuck_policy
+    }
+
+    /// Follows an included transaction until it reaches the confirmation target, polling at the
+    /// poll strategy's pace and following it through reorgs. Times are measured from the call,
+    /// i.e. from the first receipt.
+    pub async fn track_confirmations(&self, receipt: &TransactionReceipt) -> Result<ConfirmationOutcome> {
+        confirmation::track(&self.client, receipt, self.confirmation_target, self.poll_strategy).await
+    }
+
+    /// Sends the transaction with the given nonce and waits until it is included.
+    /// `index` is only used to label progress output.
+    pub async fn submit(&self, index: u64, nonce: u64) -> Result<Submission> {
+        self.submit_with(index, nonce, &self.gas_strategy).await
+    }
+
+    /// Like [`submit`](Self::submit), with fees chosen by `gas_strategy` instead of the submitter's own
+    pub async fn submit_with(&self, index: u64, nonce: u64, gas_strategy: &GasStrategy) -> Result<Submission> {
+        match &self.sync_submitter {
+            None => self.send_and_confirm_transaction(nonce, gas_strategy).await,
+            Some(sync_submitter) => self.send_sync_transaction(sync_submitter, index, nonce, gas_strategy).await,
+        }
+    }
+
+    /// Sends a transaction and waits for the receipt
+    /// This version removes unnecessary await calls to minimize RPC requests
+    async fn send_and_confirm_transaction(&self, nonce: u64, gas_strategy: &GasStrategy) -> Result<Submission> {
+        let fill_start = Instant::now();
+        let filled = self.fill_transaction(nonce, gas_strategy).await?;
+        let fill = fill_start.elapsed();
+        let (fee_per_gas, priority_fee) = fees_of(&filled.tx);
+
+        // Sign locally rather than through SignerMiddleware, so signing isn't counted as send time
+        let (raw_tx, tx_hash, sign, encode) = self.sign_and_encode(&filled).await?;
+        let observations = self.observe(tx_hash);
+
+        // Start measuring send time
+        let sent_at = SystemTime::now();
+        let send_start = Instant::now();
+
+        // Send transaction
+        let pending_tx = self.client.send_raw_transaction(raw_tx.clone()).await?;
+        let tx_hash = pending_tx.tx_hash();
+
+        // Measure send time
+        let send_time = send_start.elapsed();
+        info!("TX sent in {:?}, hash: {}", send_time, tx_hash);
+
+        // Start measuring confirmation time
+        let confirm_start = Instant::now();
+
+        // Wait for receipt
+        info!("Waiting for confirmation...");
+        let mut chain = ReplacementChain::new(filled.tx, raw_tx, tx_hash, send_start);
+        let (receipt, receipt_calls) = match &self.block_watcher {
+            Some(block_watcher) if !block_watcher.is_closed() => self.wait_for_block(block_watcher, &mut chain).await?,
+            _ => self.poll_receipt(&mut chain).await?,
+        };
+        log_receipt(&receipt);
+        let cancelled = chain.is_cancellation(receipt.transaction_hash);
+
+        // Measure confirmation time
+        let confirm_time = confirm_start.elapsed();
+        let to_receipt = send_start.elapsed();
+        info!("TX confirmed in {:?} after {} receipt calls", confirm_time, receipt_calls);
+
+        // Get block information
+        if let Some(block_number) = receipt.block_number {
+            info!("Included in block: {}", block_number);
+        }
+
+        Ok(Submission {
+            receipt,
+            sent_at,
+            send_time,
+            confirm_time,
+            phases: PhaseTimings { fill, sign, encode, to_receipt, ..Default::default() },
+            receipt_calls: Some(receipt_calls),
+            observations,
+            fee_per_gas,
+            priority_fee,
+            replacements: chain.into_replacements(),
+            cancelled,
+        })
+    }
+
+    /// Builds the transaction of the configured type with an explicit nonce, the workload's
+    /// recipient, value and calldata, its gas limit and the fees `gas_strategy` chooses now
+    async fn fill_transaction(&self, nonce: u64, gas_strategy: &GasStrategy) -> Result<FilledTx> {
+        let address = self.client.address();
+        let fees = gas_strategy.fees(&self.client).await?;
+        let mut max_fee_per_blob_gas = None;
+        let mut tx: TypedTransaction = match self.tx_type {
+            TxType::Legacy => TransactionRequest::new().gas_price(fees.gas_price).into(),
+            TxType::Eip2930 => TransactionRequest::new()
+                .gas_price(fees.gas_price)
+                .with_access_list(AccessList::default())
+                .into(),
+            TxType::Eip1559 => Eip1559TransactionRequest::new()
+                .max_fee_per_gas(fees.max_fee_per_gas)
+                .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+                .into(),
+            TxType::Eip4844 => {
+                max_fee_per_blob_gas = Some(gas::max_fee_per_blob_gas(&self.client).await?);
+                Eip1559TransactionRequest::new()
+                    .max_fee_per_gas(fees.max_fee_per_gas)
+                    .max_priority_fee_per_gas(fees.max_priority_fee_per_gas)
+                    .into()
+            }
+        };
+        tx.set_from(address);
+        self.workload.apply(&mut tx, address);
+        tx.set_nonce(nonce);
+        tx.set_chain_id(self.client.signer().chain_id());
+        tx.set_gas(self.gas_limit);
+        Ok(FilledTx { tx, max_fee_per_blob_gas })
+    }
+
+    /// Most a single transaction can cost the sender: its gas limit at the highest fee it pays,
+    /// including fee bumps of the stuck policy, plus the value it transfers. Fees that follow the
+    /// chain are taken at twice their current level, since they may rise during the run.
+    pub async fn max_cost(&self) -> Result<U256> {
+        let FilledTx { tx, max_fee_per_blob_gas } = self.fill_transaction(0, &self.gas_strategy).await?;
+        let gas = tx.gas().copied().unwrap_or_default();
+        let mut fee_per_gas = tx.gas_price().unwrap_or_default();
+        if !self.gas_strategy.is_fixed() {
+            fee_per_gas *= 2;
+        }
+        if let Some(stuck_policy) = &self.stuck_policy {
+            fee_per_gas = stuck_policy.max_fee(fee_per_gas);
+        }
+        let blob_fee = max_fee_per_blob_gas.unwrap_or_default() * BLOB_GAS_PER_BLOB;
+        Ok(gas * fee_per_gas + blob_fee + tx.value().copied().unwrap_or_default())
+    }
+
+    /// Signs the transaction with the client's wallet and RLP-encodes it according to EIP-2718,
+    /// with the blob sidecar for blob transactions. Returns the raw transaction and its hash along
+    /// with the signing and encoding times.
+    async fn sign_and_encode(&self, filled: &FilledTx) -> Result<(Bytes, H256, Duration, Duration)> {
+        if let Some(max_fee_per_blob_gas) = filled.max_fee_per_blob_gas {
+            return self.sign_and_encode_blob(&filled.tx, max_fee_per_blob_gas);
+        }
+
+        let sign_start = Instant::now();
+        let signature = self.client.signer().sign_transaction(&filled.tx).await?;
+        let sign = sign_start.elapsed();
+
+        let encode_start = Instant::now();
+        let raw_tx = filled.tx.rlp_signed(&signature);
+        let encode = encode_start.elapsed();
+
+        let tx_hash = H256::from(keccak256(&raw_tx));
+        Ok((raw_tx, tx_hash, sign, encode))
+    }
+
+    /// Signs and encodes a blob transaction, which ethers can't do itself
+    fn sign_and_encode_blob(&self, tx: &TypedTransaction, max_fee_per_blob_gas: U256) -> Result<(Bytes, H256, Duration, Duration)> {
+        let TypedTransaction::Eip1559(tx) = tx else {
+            return Err(anyhow!("blob transactions are built from EIP-1559 fields"));
+        };
+        let sidecar = self.blob_sidecar.as_ref().ok_or_else(|| anyhow!("no blob sidecar for a blob transaction"))?;
+        let blob_tx = BlobTransaction::new(tx, max_fee_per_blob_gas, sidecar);
+
+        let sign_start = Instant::now();
+        let signature = self.client.signer().sign_hash(blob_tx.sighash()?)?;
+        let sign = sign_start.elapsed();
+
+        let encode_start = Instant::now();
+        let (raw_tx, tx_hash) = blob_tx.encode_signed(&signature)?;
+        let encode = encode_start.elapsed();
+
+        Ok((raw_tx, tx_hash, sign, encode))
+    }
+
+    /// Starts watching the observers for the signed transaction, just before it is sent
+    fn observe(&self, tx_hash: H256) -> PendingObservations {
+        if self.observers.is_empty() {
+            return PendingObservations::default();
+        }
+        PendingObservations::start(&self.observers, tx_hash)
+    }
+
+    /// Polls for the receipt of any transaction in the chain until one is included, following the
+    /// poll strategy and applying the stuck policy between polls.
+    /// Returns the receipt and the number of `eth_getTransactionReceipt` calls it took.
+    async fn poll_receipt(&self, chain: &mut ReplacementChain) -> Result<(TransactionReceipt, u32)> {
+        let mut polls = 0;
+        let mut calls = 0;
+        loop {
+            polls += 1;
+            calls += chain.hashes().len() as u32;
+            match chain.receipt(&self.client).await? {
+                Some(r) => return Ok((r, calls)),
+                None => {
+                    if let Some(stuck_policy) = &self.stuck_policy {
+                        chain.check(&self.client, stuck_policy).await?;
+                    }
+                    let delay = self.poll_strategy.delay(polls);
+                    if !delay.is_zero() {
+                        sleep(delay).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Waits for the block that includes any transaction in the chain to arrive on the `newHeads`
+    /// subscription, then fetches the receipt. With a stuck policy, the wait is cut short whenever
+    /// the policy is due, so it can act and watch the replacement too.
+    async fn wait_for_block(&self, block_watcher: &BlockWatcher, chain: &mut ReplacementChain) -> Result<(TransactionReceipt, u32)> {
+        let mut calls = 0;
+        loop {
+            let included = future::select_all(chain.hashes().iter().map(|&tx_hash| block_watcher.watch(tx_hash)));
+
+            // The block may have arrived while the transaction was still being sent
+            calls += chain.hashes().len() as u32;
+            if let Some(r) = chain.receipt(&self.client).await? {
+                unwatch_all(block_watcher, chain);
+                return Ok((r, calls));
+            }
+
+            let (block_number, ..) = match &self.stuck_policy {
+                None => included.await,
+                Some(stuck_policy) => match tokio::time::timeout(chain.until_due(stuck_policy), included).await {
+                    Ok(included) => included,
+                    Err(_) => {
+                        unwatch_all(block_watcher, chain);
+                        chain.check(&self.client, stuck_policy).await?;
+                        continue;
+                    }
+                },
+            };
+            unwatch_all(block_watcher, chain);
+            let Ok(block_number) = block_number else {
+                warn!("newHeads subscription ended while waiting for {:?}; polling for its receipt", chain.hashes()[0]);
+                let (receipt, more_calls) = self.poll_receipt(chain).await?;
+                return Ok((receipt, calls + more_calls));
+            };
+            info!("Seen in block {} via newHeads", block_number);
+
+            // The node announced the block, so the receipt is normally there on the first try
+            let (receipt, more_calls) = self.poll_receipt(chain).await?;
+            return Ok((receipt, calls + more_calls));
+        }
+    }
+
+    /// Signs a transaction locally and submits it with a synchronous RPC method
+    /// (e.g. `eth_sendRawTransactionSync` or `realtime_sendRawTransaction`), which returns the receipt directly
+    async fn send_sync_transaction(
+        &self,
+        sync_submitter: &impl SyncSubmit,
+        index: u64,
+        nonce: u64,
+        gas_strategy: &GasStrategy,
+    ) -> Result<Submission> {
+        let fill_start = Instant::now();
+        let filled = self.fill_transaction(nonce, gas_strategy).await?;
+        let fill = fill_start.elapsed();
+        let (fee_per_gas, priority_fee) = fees_of(&filled.tx);
+
+        // Sign the transaction and get the properly encoded transaction according to EIP-2718
+        let (raw_tx, tx_hash, sign, encode) = self.sign_and_encode(&filled).await?;
+        let observations = self.observe(tx_hash);
+
+        // Start measuring send time
+        let sent_at = SystemTime::now();
+        let send_start = Instant::now();
+
+        info!("Sending TX #{} with {}...", index + 1, sync_submitter.method_name());
+        let receipt = sync_submitter.send_raw_and_wait(raw_tx).await?;
+        let send_time = send_start.elapsed();
+
+        log_receipt(&receipt);
+
+        // Print block information
+        if let Some(block_number) = receipt.block_number {
+            info!("Included in block: {}", block_number);
+        }
+
+        Ok(Submission {
+            receipt,
+            sent_at,
+            send_time,
+            confirm_time: Duration::default(),
+            // The submission call itself returns the receipt
+            phases: PhaseTimings { fill, sign, encode, to_receipt: send_time, ..Default::default() },
+            receipt_calls: None,
+            observations,
+            fee_per_gas,
+            priority_fee,
+            replacements: Vec::new(),
+            cancelled: false,
+        })
+    }
+}
+
+/// A transaction ready to sign, and the blob fee cap of a blob transaction, whose other fields are
+/// those of an EIP-1559 transaction
+struct FilledTx {
+    tx: TypedTransaction,
+    max_fee_per_blob_gas: Option<U256>,
+}
+
+/// Gas price or max fee per gas of a filled transaction, and its priority fee if it has one
+fn fees_of(tx: &TypedTransaction) -> (U256, Option<U256>) {
+    let priority_fee = match tx {
+        TypedTransaction::Eip1559(tx) => tx.max_priority_fee_per_gas,
+        _ => None,
+    };
+    (tx.gas_price().unwrap_or_default(), priority_fee)
+}
+
+/// Stops watching every transaction in the chain, e.g. because one of them was found
+fn unwatch_all(block_watcher: &BlockWatcher, chain: &ReplacementChain) {
+    for &tx_hash in chain.hashes() {
+        block_watcher.unwatch(tx_hash);
+    }
+}
+
+/// Logs the transaction status in a more readable format
+fn log_receipt(receipt: &TransactionReceipt) {
+    info!("\n====== TRANSACTION RECEIPT ======");
+    info!("Transaction Hash: {:?}", receipt.transaction_hash);
+    info!("Transaction Status: {}", TxStatus::from_receipt(receipt));
+    info!("Block Number: {:?}", receipt.block_number);
+    info!("Gas Used: {:?}", receipt.gas_used);
+    info!("================================");
+}
@@ -0,0 +1,96 @@
+use anyhow::{anyhow, Result};
+use ethers::types::H256;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tokio::{sync::oneshot, task::JoinHandle};
+
+/// Transaction hashes being waited for, with the sender that wakes each waiter; `None` once the
+/// subscription has ended
+pub type Waiters<T> = Arc<Mutex<Option<HashMap<H256, oneshot::Sender<T>>>>>;
+
+/// A task following a WebSocket subscription that wakes whoever is waiting for a transaction
+/// hash with a `T`. The task is aborted when this is dropped.
+#[derive(Debug)]
+pub struct HashSubscription<T> {
+    waiters: Waiters<T>,
+    task: JoinHandle<()>,
+}
+
+impl<T: Send + 'static> HashSubscription<T> {
+    /// Spawns `follow` with the waiters and returns once it has subscribed. `follow` reports
+    /// whether subscribing worked through its `ready` sender (see [`subscribed`]), then wakes
+    /// waiters (see [`wake`]) until the subscription ends, which closes the subscription.
+    pub async fn spawn<F, Fut>(name: &str, follow: F) -> Result<Self>
+    where
+        F: FnOnce(Waiters<T>, oneshot::Sender<Result<()>>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let waiters: Waiters<T> = Arc::new(Mutex::new(Some(HashMap::new())));
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let following = follow(waiters.clone(), ready_tx);
+        let closing = waiters.clone();
+        let task = tokio::spawn(async move {
+            following.await;
+            // Dropping the senders makes pending receivers fail, and later ones fail right away
+            closing.lock().unwrap().take();
+        });
+        // Surface subscription errors when connecting rather than on the first transaction
+        ready_rx
+            .await
+            .map_err(|_| anyhow!("{} subscription task exited", name))??;
+        Ok(Self { waiters, task })
+    }
+
+    /// Registers interest in `tx_hash`; the receiver fails if the subscription ends first, or
+    /// right away if it already has
+    pub fn watch(&self, tx_hash: H256) -> oneshot::Receiver<T> {
+        let (tx, rx) = oneshot::channel();
+        if let Some(waiters) = self.waiters.lock().unwrap().as_mut() {
+            waiters.insert(tx_hash, tx);
+        }
+        rx
+    }
+
+    /// Stops watching `tx_hash`
+    pub fn unwatch(&self, tx_hash: H256) {
+        if let Some(waiters) = self.waiters.lock().unwrap().as_mut() {
+            waiters.remove(&tx_hash);
+        }
+    }
+
+    /// Whether the subscription has ended, so watching would never succeed
+    pub fn is_closed(&self) -> bool {
+        self.waiters.lock().unwrap().is_none()
+    }
+}
+
+impl<T> Drop for HashSubscription<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Reports the outcome of subscribing to `ready`, returning the subscription if it worked
+pub fn subscribed<S, E: Into<anyhow::Error>>(subscription: Result<S, E>, ready: oneshot::Sender<Result<()>>) -> Option<S> {
+    match subscription {
+        Ok(subscription) => {
+            let _ = ready.send(Ok(()));
+            Some(subscription)
+        }
+        Err(e) => {
+            let _ = ready.send(Err(e.into()));
+            None
+        }
+    }
+}
+
+/// Wakes whoever is waiting for `tx_hash` with `value`
+pub fn wake<T>(waiters: &Waiters<T>, tx_hash: &H256, value: T) {
+    let waiter = waiters.lock().unwrap().as_mut().and_then(|waiters| waiters.remove(tx_hash));
+    if let Some(waiter) = waiter {
+        let _ = waiter.send(value);
+    }
+}
@@ -0,0 +1,523 @@
+use async_trait::async_trait;
+use clap::ValueEnum;
+use ethers::providers::{
+    Authorization, Http, HttpClientError, JsonRpcClient, JsonRpcError, ProviderError, RpcError,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    cell::RefCell,
+    fmt::Debug,
+    future::Future,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{lookup_host, TcpStream},
+    time::timeout,
+};
+use tokio_rustls::{
+    rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName},
+    TlsConnector,
+};
+use url::Url;
+
+use crate::submitter::TxMethod;
+
+/// Longest wait for a response once the request is written. Generous, since the sync submission
+/// methods only respond once the transaction is included, but a stalled connection gives up.
+const READ_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Longest a kept-alive connection is reused after its last response. Servers close idle
+/// connections after a few seconds (Node.js after 5), and a submission written to a connection
+/// the server has just closed fails without a retry, so older ones are dropped instead.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// Whether `method` submits a transaction with any of the submission methods. A broken connection
+/// doesn't tell whether the server got the request, so these are never sent again on a new one.
+fn is_submission(method: &str) -> bool {
+    TxMethod::value_variants().iter().any(|tx_method| tx_method.submit_rpc_method() == method)
+}
+
+tokio::task_local! {
+    /// Timings of the JSON-RPC calls made by the current task, if it is recording them
+    static HTTP_TIMINGS: RefCell<Vec<HttpTiming>>;
+}
+
+/// Runs `future` and collects the timings of every instrumented JSON-RPC call it makes
+pub async fn record_http_timings<F: Future>(future: F) -> (F::Output, Vec<HttpTiming>) {
+    HTTP_TIMINGS
+        .scope(RefCell::new(Vec::new()), async {
+            let output = future.await;
+            (output, HTTP_TIMINGS.with(|timings| timings.take()))
+        })
+        .await
+}
+
+/// Connection-level timing of a single JSON-RPC call over the instrumented transport.
+/// The setup phases are zero when the call reused a kept-alive connection.
+#[derive(Debug, Clone)]
+pub struct HttpTiming {
+    pub rpc_method: String,
+    /// Whether the call went over a kept-alive connection instead of opening a new one
+    pub reused: bool,
+    pub dns: Duration,
+    pub tcp_connect: Duration,
+    /// Zero for plain HTTP
+    pub tls_handshake: Duration,
+    /// Writing the request headers and body
+    pub write: Duration,
+    /// From the end of the request until the first byte of the response
+    pub ttfb: Duration,
+    /// The whole call, including connection setup and reading the response
+    pub total: Duration,
+}
+
+/// JSON-RPC transport used by the benchmark client: the regular ethers HTTP client, or
+/// [`InstrumentedHttp`] when connection-level timings are wanted. Clones share connections.
+#[derive(Debug, Clone)]
+pub enum Transport {
+    Http(Http),
+    Instrumented(Arc<InstrumentedHttp>),
+}
+
+impl Transport {
+    pub fn http(url: &str) -> anyhow::Result<Self> {
+        Ok(Transport::Http(Http::from_str(url)?))
+    }
+
+    pub fn instrumented(url: &str) -> anyhow::Result<Self> {
+        Ok(Transport::Instrumented(Arc::new(InstrumentedHttp::new(url)?)))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TransportError {
+    #[error(transparent)]
+    Http(#[from] HttpClientError),
+
+    #[error(transparent)]
+    Instrumented(#[from] InstrumentedError),
+}
+
+impl RpcError for TransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            TransportError::Http(e) => e.as_error_response(),
+            TransportError::Instrumented(e) => e.as_error_response(),
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            TransportError::Http(e) => e.as_serde_error(),
+            TransportError::Instrumented(e) => e.as_serde_error(),
+        }
+    }
+}
+
+impl From<TransportError> for ProviderError {
+    fn from(src: TransportError) -> Self {
+        match src {
+            TransportError::Http(e) => e.into(),
+            TransportError::Instrumented(e) => ProviderError::JsonRpcClientError(Box::new(e)),
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for Transport {
+    type Error = TransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            Transport::Http(http) => Ok(http.request(method, params).await?),
+            Transport::Instrumented(http) => Ok(http.request(method, params).await?),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum InstrumentedError {
+    #[error("invalid RPC URL: {0}")]
+    Url(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("HTTP {status}: {body}")]
+    Status { status: u16, body: String },
+
+    #[error("malformed HTTP response: {0}")]
+    Response(String),
+
+    #[error("no response within {0:?}")]
+    Timeout(Duration),
+
+    #[error("failed to deserialize response: {err}: {text}")]
+    SerdeJson { err: serde_json::Error, text: String },
+
+    #[error(transparent)]
+    JsonRpcError(#[from] JsonRpcError),
+}
+
+impl RpcError for InstrumentedError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            InstrumentedError::JsonRpcError(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            InstrumentedError::SerdeJson { err, .. } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+trait Io: AsyncRead + AsyncWrite + Unpin + Send + Debug {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Debug> Io for T {}
+
+type Connection = BufReader<Box<dyn Io>>;
+
+/// Minimal HTTP/1.1 JSON-RPC client that times every phase of each call: DNS resolution,
+/// TCP connect, TLS handshake, request write and time to first byte. Connections are kept
+/// alive and reused for up to [`IDLE_TIMEOUT`], and each call records whether it opened a new one.
+#[derive(Debug)]
+pub struct InstrumentedHttp {
+    host: String,
+    port: u16,
+    /// Request target, i.e. path and query
+    target: String,
+    authorization: Option<String>,
+    /// Set for https URLs
+    tls: Option<Arc<ClientConfig>>,
+    next_id: AtomicU64,
+    /// Kept-alive connections with the time each one was last used
+    idle: Mutex<Vec<(Connection, Instant)>>,
+}
+
+impl InstrumentedHttp {
+    pub fn new(url: &str) -> Result<Self, InstrumentedError> {
+        let url = Url::parse(url).map_err(|e| InstrumentedError::Url(e.to_string()))?;
+        let tls = match url.scheme() {
+            "http" => None,
+            "https" => Some(tls_config()),
+            scheme => return Err(InstrumentedError::Url(format!("unsupported scheme '{}'", scheme))),
+        };
+        let host = url
+            .host_str()
+            .ok_or_else(|| InstrumentedError::Url("missing host".to_string()))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| InstrumentedError::Url("missing port".to_string()))?;
+        let target = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let authorization = (!url.username().is_empty())
+            .then(|| Authorization::basic(url.username(), url.password().unwrap_or_default()).to_string());
+
+        Ok(Self {
+            host,
+            port,
+            target,
+            authorization,
+            tls,
+            next_id: AtomicU64::new(1),
+            idle: Mutex::new(Vec::new()),
+        })
+    }
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, InstrumentedError>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let mut payload = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::SeqCst),
+            "method": method,
+        });
+        // Like the ethers HTTP client, leave out params for calls that take none
+        let params = serde_json::to_value(params)
+            .map_err(|err| InstrumentedError::SerdeJson { err, text: String::new() })?;
+        if !params.is_null() {
+            payload["params"] = params;
+        }
+        let body = payload.to_string();
+
+        let mut timing = HttpTiming {
+            rpc_method: method.to_string(),
+            reused: false,
+            dns: Duration::ZERO,
+            tcp_connect: Duration::ZERO,
+            tls_handshake: Duration::ZERO,
+            write: Duration::ZERO,
+            ttfb: Duration::ZERO,
+            total: Duration::ZERO,
+        };
+        let start = Instant::now();
+        let result = self.call(method, &body, &mut timing).await;
+        // Failed calls are recorded too, as their timings tell where they failed
+        timing.total = start.elapsed();
+        let _ = HTTP_TIMINGS.try_with(|timings| timings.borrow_mut().push(timing));
+
+        let (status, response) = result?;
+        decode_response(status, &response)
+    }
+
+    /// Sends `body` over a kept-alive connection if there is one, or a new one otherwise, and
+    /// returns the response's status and body
+    async fn call(&self, method: &str, body: &str, timing: &mut HttpTiming) -> Result<(u16, Vec<u8>), InstrumentedError> {
+        let idle = {
+            let mut idle = self.idle.lock().unwrap();
+            idle.retain(|(_, since)| since.elapsed() < IDLE_TIMEOUT);
+            idle.pop().map(|(connection, _)| connection)
+        };
+        if let Some(mut connection) = idle {
+            timing.reused = true;
+            match self.exchange(&mut connection, body, timing).await {
+                Ok(response) => return Ok(self.finish(connection, response)),
+                // The server may have closed the idle connection; retry once on a new one if no
+                // response arrived. The server may still have processed the request, which is
+                // harmless for reads but not for submissions, so their errors are surfaced instead.
+                Err(e) if !timing.ttfb.is_zero() || is_submission(method) => return Err(e),
+                Err(_) => timing.reused = false,
+            }
+        }
+
+        let mut connection = self.connect(timing).await?;
+        let response = self.exchange(&mut connection, body, timing).await?;
+        Ok(self.finish(connection, response))
+    }
+
+    /// Opens a new connection, recording DNS, TCP connect and TLS handshake times
+    async fn connect(&self, timing: &mut HttpTiming) -> Result<Connection, InstrumentedError> {
+        let dns_start = Instant::now();
+        let addrs: Vec<_> = lookup_host((self.host.as_str(), self.port)).await?.collect();
+        timing.dns = dns_start.elapsed();
+
+        let connect_start = Instant::now();
+        let mut last_error = None;
+        let mut stream = None;
+        for addr in addrs {
+            match TcpStream::connect(addr).await {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        let stream = match (stream, last_error) {
+            (Some(stream), _) => stream,
+            (None, Some(e)) => return Err(e.into()),
+            (None, None) => return Err(InstrumentedError::Url(format!("{} did not resolve", self.host))),
+        };
+        stream.set_nodelay(true)?;
+        timing.tcp_connect = connect_start.elapsed();
+
+        let io: Box<dyn Io> = match &self.tls {
+            Some(config) => {
+                let server_name = ServerName::try_from(self.host.as_str())
+                    .map_err(|e| InstrumentedError::Url(e.to_string()))?;
+                let tls_start = Instant::now();
+                let stream = TlsConnector::from(config.clone()).connect(server_name, stream).await?;
+                timing.tls_handshake = tls_start.elapsed();
+                Box::new(stream)
+            }
+            None => Box::new(stream),
+        };
+        Ok(BufReader::new(io))
+    }
+
+    /// Writes the request and reads the response, recording write time and time to first byte, and
+    /// gives up if the response takes longer than [`READ_TIMEOUT`]
+    async fn exchange(
+        &self,
+        connection: &mut Connection,
+        body: &str,
+        timing: &mut HttpTiming,
+    ) -> Result<Response, InstrumentedError> {
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: keep-alive\r\n",
+            self.target,
+            self.host_header(),
+            body.len()
+        );
+        if let Some(authorization) = &self.authorization {
+            request.push_str(&format!("Authorization: {}\r\n", authorization));
+        }
+        request.push_str("\r\n");
+        request.push_str(body);
+
+        let write_start = Instant::now();
+        connection.write_all(request.as_bytes()).await?;
+        connection.flush().await?;
+        timing.write = write_start.elapsed();
+
+        let ttfb_start = Instant::now();
+        let first_bytes = timeout(READ_TIMEOUT, connection.fill_buf())
+            .await
+            .map_err(|_| InstrumentedError::Timeout(READ_TIMEOUT))??;
+        if first_bytes.is_empty() {
+            return Err(InstrumentedError::Response("connection closed before the response".to_string()));
+        }
+        timing.ttfb = ttfb_start.elapsed();
+
+        timeout(READ_TIMEOUT, read_response(connection))
+            .await
+            .map_err(|_| InstrumentedError::Timeout(READ_TIMEOUT))?
+    }
+
+    /// Returns the connection to the idle pool unless the server is closing it
+    fn finish(&self, connection: Connection, response: Response) -> (u16, Vec<u8>) {
+        if response.keep_alive {
+            self.idle.lock().unwrap().push((connection, Instant::now()));
+        }
+        (response.status, response.body)
+    }
+
+    fn host_header(&self) -> String {
+        let default_port = if self.tls.is_some() { 443 } else { 80 };
+        let host = if self.host.contains(':') { format!("[{}]", self.host) } else { self.host.clone() };
+        if self.port == default_port { host } else { format!("{}:{}", host, self.port) }
+    }
+}
+
+fn tls_config() -> Arc<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+    }));
+    let mut config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Arc::new(config)
+}
+
+struct Response {
+    status: u16,
+    keep_alive: bool,
+    body: Vec<u8>,
+}
+
+/// Reads an HTTP/1.1 response with either a `Content-Length` or a chunked body
+async fn read_response(connection: &mut Connection) -> Result<Response, InstrumentedError> {
+    let status_line = read_line(connection).await?;
+    let mut parts = status_line.split_whitespace();
+    let version = parts.next().unwrap_or_default().to_string();
+    let status = parts
+        .next()
+        .and_then(|s| s.parse::<u16>().ok())
+        .ok_or_else(|| InstrumentedError::Response(format!("bad status line '{}'", status_line)))?;
+
+    let mut content_length = None;
+    let mut chunked = false;
+    let mut keep_alive = version == "HTTP/1.1";
+    loop {
+        let line = read_line(connection).await?;
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_ascii_lowercase();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => {
+                content_length = Some(
+                    value
+                        .parse::<usize>()
+                        .map_err(|_| InstrumentedError::Response(format!("bad content-length '{}'", value)))?,
+                )
+            }
+            "transfer-encoding" => chunked = value.contains("chunked"),
+            "connection" => keep_alive = !value.contains("close"),
+            _ => {}
+        }
+    }
+
+    let body = if chunked {
+        read_chunked(connection).await?
+    } else if let Some(length) = content_length {
+        let mut body = vec![0; length];
+        connection.read_exact(&mut body).await?;
+        body
+    } else {
+        // No framing: the body runs until the server closes the connection
+        keep_alive = false;
+        let mut body = Vec::new();
+        connection.read_to_end(&mut body).await?;
+        body
+    };
+
+    Ok(Response { status, keep_alive, body })
+}
+
+async fn read_chunked(connection: &mut Connection) -> Result<Vec<u8>, InstrumentedError> {
+    let mut body = Vec::new();
+    loop {
+        let size_line = read_line(connection).await?;
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| InstrumentedError::Response(format!("bad chunk size '{}'", size_line)))?;
+        if size == 0 {
+            // Skip any trailers
+            while !read_line(connection).await?.is_empty() {}
+            return Ok(body);
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        connection.read_exact(&mut body[start..]).await?;
+        read_line(connection).await?;
+    }
+}
+
+/// Reads one CRLF-terminated line, without the line ending
+async fn read_line(connection: &mut Connection) -> Result<String, InstrumentedError> {
+    let mut line = String::new();
+    if connection.read_line(&mut line).await? == 0 {
+        return Err(InstrumentedError::Response("unexpected end of response".to_string()));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Extracts the result or error from a JSON-RPC response body
+fn decode_response<R: DeserializeOwned>(status: u16, body: &[u8]) -> Result<R, InstrumentedError> {
+    let text = || String::from_utf8_lossy(body).to_string();
+    let mut response: serde_json::Value = match serde_json::from_slice(body) {
+        Ok(response) => response,
+        Err(_) if status != 200 => return Err(InstrumentedError::Status { status, body: text() }),
+        Err(err) => return Err(InstrumentedError::SerdeJson { err, text: text() }),
+    };
+
+    if let Some(error) = response.get_mut("error") {
+        let error: JsonRpcError = serde_json::from_value(error.take())
+            .map_err(|err| InstrumentedError::SerdeJson { err, text: text() })?;
+        return Err(error.into());
+    }
+    match response.get_mut("result") {
+        Some(result) => {
+            serde_json::from_value(result.take()).map_err(|err| InstrumentedError::SerdeJson { err, text: text() })
+        }
+        None => Err(InstrumentedError::Status { status, body: text() }),
+    }
+}
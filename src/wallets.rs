@@ -0,0 +1,223 @@
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{self, Token},
+    providers::{Middleware, PendingTransaction},
+    signers::{coins_bip39::English, LocalWallet, MnemonicBuilder, Signer},
+    types::{transaction::eip2718::TypedTransaction, Address, BlockNumber, TransactionRequest, H256, U256},
+    utils::{id, keccak256},
+};
+use log::{info, warn};
+use std::time::Duration;
+
+use crate::gas::GasStrategy;
+use crate::workload::erc20_transfer;
+use crate::Client;
+
+/// How often funding and sweep transactions are polled for their receipts
+const SETUP_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Gas limit of the plain ETH transfers that fund and sweep the sender wallets
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Derives `count` sender wallets: accounts `m/44'/60'/0'/0/0..count` of `mnemonic` if given,
+/// otherwise keys hashed from the main wallet's key, so the same main key always yields the same
+/// senders and leftover funds can be recovered
+pub fn derive(main: &LocalWallet, mnemonic: Option<&str>, count: usize) -> Result<Vec<LocalWallet>> {
+    (0..count)
+        .map(|i| {
+            let wallet = match mnemonic {
+                Some(phrase) => MnemonicBuilder::<English>::default().phrase(phrase).index(i as u32)?.build()?,
+                None => {
+                    let mut seed = main.signer().to_bytes().to_vec();
+                    seed.extend_from_slice(b"tx-latency sender");
+                    seed.extend_from_slice(&(i as u64).to_be_bytes());
+                    LocalWallet::from_bytes(&keccak256(seed))?
+                }
+            };
+            Ok(wallet.with_chain_id(main.chain_id()))
+        })
+        .collect()
+}
+
+/// Tops every wallet up to `amount` from `funder`, sending all transfers before waiting for any.
+/// Wallets that already hold `amount`, or are the funder itself, are left alone. Nonces start at
+/// the funder's pending count, after any transaction it still has in flight.
+pub async fn fund(funder: &Client, wallets: &[Address], amount: U256, gas_price: U256) -> Result<()> {
+    let mut nonce = funder.get_transaction_count(funder.address(), Some(BlockNumber::Pending.into())).await?;
+    let mut pending = Vec::new();
+    for &wallet in wallets.iter().filter(|&&wallet| wallet != funder.address()) {
+        let balance = funder.get_balance(wallet, None).await?;
+        if balance >= amount {
+            info!("Sender {:?} already holds {} wei", wallet, balance);
+            continue;
+        }
+        let tx = TransactionRequest::new()
+            .from(funder.address())
+            .to(wallet)
+            .value(amount - balance)
+            .nonce(nonce)
+            .gas(TRANSFER_GAS)
+            .gas_price(gas_price);
+        let tx_hash = funder.send_transaction(tx, None).await?.tx_hash();
+        info!("Funding sender {:?} with {} wei: {:?}", wallet, amount - balance, tx_hash);
+        pending.push(tx_hash);
+        nonce += U256::one();
+    }
+    wait_for_funding(funder, pending).await
+}
+
+/// Tops every wallet's balance of the ERC-20 `token` up to `amount` from `funder`, like [`fund`]
+/// does with ETH, so senders of an ERC-20 workload have tokens to transfer
+pub async fn fund_tokens(funder: &Client, token: Address, wallets: &[Address], amount: U256, gas_price: U256) -> Result<()> {
+    let mut nonce = funder.get_transaction_count(funder.address(), Some(BlockNumber::Pending.into())).await?;
+    let mut pending = Vec::new();
+    for &wallet in wallets.iter().filter(|&&wallet| wallet != funder.address()) {
+        let balance = token_balance(funder, token, wallet).await?;
+        if balance >= amount {
+            info!("Sender {:?} already holds {} tokens", wallet, balance);
+            continue;
+        }
+        let tx = TransactionRequest::new()
+            .from(funder.address())
+            .to(token)
+            .data(erc20_transfer(wallet, amount - balance))
+            .nonce(nonce)
+            .gas_price(gas_price);
+        let tx_hash = funder.send_transaction(tx, None).await?.tx_hash();
+        info!("Funding sender {:?} with {} tokens: {:?}", wallet, amount - balance, tx_hash);
+        pending.push(tx_hash);
+        nonce += U256::one();
+    }
+    wait_for_funding(funder, pending).await
+}
+
+/// Waits for every funding transaction and fails if any of them was dropped or reverted
+async fn wait_for_funding(funder: &Client, pending: Vec<H256>) -> Result<()> {
+    for tx_hash in pending {
+        let receipt = PendingTransaction::new(tx_hash, funder.provider())
+            .interval(SETUP_POLL_INTERVAL)
+            .await?
+            .ok_or_else(|| anyhow!("funding transaction {:?} was dropped", tx_hash))?;
+        if receipt.status.is_some_and(|status| status.is_zero()) {
+            return Err(anyhow!("funding transaction {:?} reverted", tx_hash));
+        }
+    }
+    Ok(())
+}
+
+/// ERC-20 `balanceOf(owner)` on `token`
+async fn token_balance(client: &Client, token: Address, owner: Address) -> Result<U256> {
+    let mut data = id("balanceOf(address)").to_vec();
+    data.extend(abi::encode(&[Token::Address(owner)]));
+    let tx: TypedTransaction = TransactionRequest::new().to(token).data(data).into();
+    let output = client.call(&tx, None).await?;
+    if output.len() < 32 {
+        return Err(anyhow!("{:?} returned no token balance; is it an ERC-20 contract?", token));
+    }
+    Ok(U256::from_big_endian(&output[..32]))
+}
+
+/// Sends each sender's balance of the ERC-20 `token` back to `to`, at the gas price `gas_strategy`
+/// chooses at the time, and waits for the transfers. Run it before [`sweep`], which takes the ETH
+/// the transfers pay their fees with. Failures are logged like [`sweep`]'s. Returns the total
+/// amount of tokens swept.
+pub async fn sweep_tokens(senders: &[&Client], token: Address, to: Address, gas_strategy: &GasStrategy) -> U256 {
+    let mut pending = Vec::new();
+    for &sender in senders.iter().filter(|sender| sender.address() != to) {
+        let address = sender.address();
+        let (balance, nonce) = match tokio::try_join!(
+            token_balance(sender, token, address),
+            async { Ok(sender.get_transaction_count(address, Some(BlockNumber::Pending.into())).await?) },
+        ) {
+            Ok(state) => state,
+            Err(e) => {
+                warn!("Failed to fetch the token balance of {:?}: {}", address, e);
+                continue;
+            }
+        };
+        if balance.is_zero() {
+            continue;
+        }
+        let gas_price = match gas_strategy.fees(sender).await {
+            Ok(fees) => fees.gas_price,
+            Err(e) => {
+                warn!("Failed to price the token sweep of {:?}: {}", address, e);
+                continue;
+            }
+        };
+        let tx = TransactionRequest::new()
+            .from(address)
+            .to(token)
+            .data(erc20_transfer(to, balance))
+            .nonce(nonce)
+            .gas_price(gas_price);
+        match sender.send_transaction(tx, None).await {
+            Ok(tx) => pending.push((sender, tx.tx_hash(), balance)),
+            Err(e) => warn!("Failed to sweep the tokens of {:?}: {}", address, e),
+        }
+    }
+
+    let mut swept = U256::zero();
+    for (sender, tx_hash, value) in pending {
+        match PendingTransaction::new(tx_hash, sender.provider()).interval(SETUP_POLL_INTERVAL).await {
+            Ok(Some(receipt)) if receipt.status.is_some_and(|status| !status.is_zero()) => swept += value,
+            Ok(_) => warn!("Token sweep of {:?} failed: {:?}", sender.address(), tx_hash),
+            Err(e) => warn!("Token sweep of {:?} failed: {}", sender.address(), e),
+        }
+    }
+    swept
+}
+
+/// Sends each sender's balance, minus the transfer fee, back to `to`, at the gas price
+/// `gas_strategy` chooses at the time. The nonce comes from the node's pending count, so a
+/// benchmark transaction that never made it out can't leave the sweep stuck behind a gap.
+/// Failures are logged rather than returned, so one wallet can't keep the others' funds from
+/// coming back. Returns the total amount swept.
+pub async fn sweep(senders: &[&Client], to: Address, gas_strategy: &GasStrategy) -> U256 {
+    let mut pending = Vec::new();
+    for &sender in senders.iter().filter(|sender| sender.address() != to) {
+        let address = sender.address();
+        let (balance, nonce) = match tokio::try_join!(
+            sender.get_balance(address, None),
+            sender.get_transaction_count(address, Some(BlockNumber::Pending.into())),
+        ) {
+            Ok(state) => state,
+            Err(e) => {
+                warn!("Failed to fetch the balance of {:?}: {}", address, e);
+                continue;
+            }
+        };
+        let gas_price = match gas_strategy.fees(sender).await {
+            Ok(fees) => fees.gas_price,
+            Err(e) => {
+                warn!("Failed to price the sweep of {:?}: {}", address, e);
+                continue;
+            }
+        };
+        let fee = gas_price * TRANSFER_GAS;
+        if balance <= fee {
+            continue;
+        }
+        let tx = TransactionRequest::new()
+            .from(address)
+            .to(to)
+            .value(balance - fee)
+            .nonce(nonce)
+            .gas(TRANSFER_GAS)
+            .gas_price(gas_price);
+        match sender.send_transaction(tx, None).await {
+            Ok(tx) => pending.push((sender, tx.tx_hash(), balance - fee)),
+            Err(e) => warn!("Failed to sweep {:?}: {}", address, e),
+        }
+    }
+
+    let mut swept = U256::zero();
+    for (sender, tx_hash, value) in pending {
+        match PendingTransaction::new(tx_hash, sender.provider()).interval(SETUP_POLL_INTERVAL).await {
+            Ok(Some(receipt)) if receipt.status.is_some_and(|status| !status.is_zero()) => swept += value,
+            Ok(_) => warn!("Sweep of {:?} failed: {:?}", sender.address(), tx_hash),
+            Err(e) => warn!("Sweep of {:?} failed: {}", sender.address(), e),
+        }
+    }
+    swept
+}
@@ -0,0 +1,150 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::{
+    abi::{self, Token},
+    providers::Middleware,
+    types::{
+        transaction::eip2718::TypedTransaction, Address, Bytes, TransactionRequest, U256,
+    },
+    utils::id,
+};
+use log::info;
+use std::path::Path;
+
+use crate::Client;
+
+/// Init code of the gas burner: copies the runtime below into memory and returns it
+const BURNER_INIT_CODE: &str = "600a600c600039600a6000f3";
+/// Gas burner runtime: `JUMPDEST PUSH2 5000 GAS GT PUSH1 0 JUMPI STOP`, i.e. loops until less than
+/// 5000 gas is left, so a call uses almost exactly its gas limit
+const BURNER_RUNTIME: &str = "5b6113885a1160005700";
+
+/// Gas limit of a plain ETH transfer to an account without code
+const TRANSFER_GAS: u64 = 21_000;
+
+/// What each benchmark transaction does
+#[derive(Debug, Clone, PartialEq)]
+pub enum Workload {
+    /// Zero-value transfer to the sender itself, the cheapest possible transaction
+    SelfTransfer,
+    /// ETH transfer to another address
+    Transfer { to: Address, value: U256 },
+    /// ERC-20 `transfer(to, amount)` on `token`
+    Erc20 { token: Address, to: Address, amount: U256 },
+    /// Call to a contract with arbitrary calldata
+    Call { to: Address, data: Bytes, value: U256 },
+    /// Contract deployment; every transaction deploys a new instance
+    Deploy { init_code: Bytes },
+    /// Call to a contract that burns its whole gas limit, to see how inclusion latency varies with
+    /// execution cost. The contract is deployed at setup unless an address is given.
+    Burn { contract: Option<Address>, gas: u64 },
+}
+
+impl Workload {
+    /// Init code of the gas burner contract, also the default for [`Workload::Deploy`]
+    pub fn burner_init_code() -> Bytes {
+        format!("{}{}", BURNER_INIT_CODE, BURNER_RUNTIME)
+            .parse()
+            .expect("burner init code is valid hex")
+    }
+
+    /// Reads hex calldata or init code from a file, with or without `0x` and ignoring whitespace
+    pub fn read_hex_file(path: &Path) -> Result<Bytes> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let hex: String = contents.split_whitespace().collect();
+        hex.parse::<Bytes>()
+            .map_err(|e| anyhow!("{} doesn't contain valid hex: {}", path.display(), e))
+    }
+
+    /// Short name for reports and exports
+    pub fn name(&self) -> &'static str {
+        match self {
+            Workload::SelfTransfer => "self-transfer",
+            Workload::Transfer { .. } => "transfer",
+            Workload::Erc20 { .. } => "erc20",
+            Workload::Call { .. } => "call",
+            Workload::Deploy { .. } => "deploy",
+            Workload::Burn { .. } => "burn",
+        }
+    }
+
+    /// Deploys the gas burner if this is a burn workload without a contract address.
+    /// Uses the wallet's next nonce, so call it before reading the starting nonce.
+    pub async fn deploy_dependencies(&mut self, client: &Client) -> Result<()> {
+        if let Workload::Burn { contract: contract @ None, .. } = self {
+            let tx = TransactionRequest::new().from(client.address()).data(Self::burner_init_code());
+            let receipt = client
+                .send_transaction(tx, None)
+                .await?
+                .await?
+                .ok_or_else(|| anyhow!("gas burner deployment was dropped"))?;
+            let address = receipt
+                .contract_address
+                .ok_or_else(|| anyhow!("gas burner deployment has no contract address"))?;
+            info!("Deployed gas burner at {:?}", address);
+            *contract = Some(address);
+        }
+        Ok(())
+    }
+
+    /// Sets the recipient, value and calldata of `tx`; `from` is the sending wallet
+    pub fn apply(&self, tx: &mut TypedTransaction, from: Address) {
+        match self {
+            Workload::SelfTransfer => {
+                tx.set_to(from);
+                tx.set_value(U256::zero());
+            }
+            Workload::Transfer { to, value } => {
+                tx.set_to(*to);
+                tx.set_value(*value);
+            }
+            Workload::Erc20 { token, to, amount } => {
+                tx.set_to(*token);
+                tx.set_value(U256::zero());
+                tx.set_data(erc20_transfer(*to, *amount));
+            }
+            Workload::Call { to, data, value } => {
+                tx.set_to(*to);
+                tx.set_value(*value);
+                tx.set_data(data.clone());
+            }
+            Workload::Deploy { init_code } => {
+                tx.set_value(U256::zero());
+                tx.set_data(init_code.clone());
+            }
+            Workload::Burn { contract, .. } => {
+                if let Some(contract) = contract {
+                    tx.set_to(*contract);
+                }
+                tx.set_value(U256::zero());
+            }
+        }
+    }
+
+    /// Gas limit for every transaction: fixed for self-transfers and burns, otherwise estimated
+    /// once with `eth_estimateGas` plus a 10% margin, since state can change before inclusion
+    pub async fn gas_limit(&self, client: &Client) -> Result<u64> {
+        match self {
+            Workload::SelfTransfer => Ok(TRANSFER_GAS),
+            Workload::Burn { gas, .. } => Ok(*gas),
+            _ => {
+                let from = client.address();
+                let mut tx = TypedTransaction::Legacy(TransactionRequest::new().from(from));
+                self.apply(&mut tx, from);
+                let estimate = client
+                    .estimate_gas(&tx, None)
+                    .await
+                    .with_context(|| format!("failed to estimate gas for the {} workload", self.name()))?
+                    .as_u64();
+                Ok(estimate + estimate / 10)
+            }
+        }
+    }
+}
+
+/// Calldata of the ERC-20 `transfer(to, amount)`
+pub fn erc20_transfer(to: Address, amount: U256) -> Bytes {
+    let mut data = id("transfer(address,uint256)").to_vec();
+    data.extend(abi::encode(&[Token::Address(to), Token::Uint(amount)]));
+    data.into()
+}